@@ -0,0 +1,69 @@
+use geohash::GeohashError;
+use std::fmt;
+
+/// Errors returned by the coverage, expansion and set operations of this crate.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// A malformed geohash: empty, too long, or with a character outside the
+    /// geohash base32 alphabet.
+    InvalidHash(String),
+    /// A precision outside 1..=12.
+    InvalidPrecision(usize),
+    /// A coordinate outside [-180, 180] × [-90, 90].
+    InvalidCoordinate { lng: f64, lat: f64 },
+    /// Geohashes that must share a precision do not.
+    MixedPrecision,
+    /// A geometry that cannot be covered: unsupported type or malformed coordinates.
+    InvalidGeometry(String),
+    /// The result would hold more than `limit` cells.
+    TooManyCells { limit: usize },
+    /// The caller's cancellation hook asked to stop.
+    Interrupted,
+    /// An argument outside its accepted range, such as a `MinOverlap` fraction
+    /// outside 0..=1.
+    InvalidArgument(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidHash(msg) => write!(f, "invalid geohash: {msg}"),
+            Error::InvalidPrecision(precision) => write!(
+                f,
+                "invalid precision {precision}: accepted values are between 1 and 12, inclusive"
+            ),
+            Error::InvalidCoordinate { lng, lat } => write!(
+                f,
+                "invalid coordinate ({lng}, {lat}): longitude must be within [-180, 180] \
+                 and latitude within [-90, 90]"
+            ),
+            Error::MixedPrecision => write!(f, "all geohashes must have the same precision"),
+            Error::InvalidGeometry(msg) => write!(f, "{msg}"),
+            Error::TooManyCells { limit } => {
+                write!(f, "the result would exceed max_cells={limit} cells")
+            }
+            Error::Interrupted => write!(f, "interrupted"),
+            Error::InvalidArgument(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<GeohashError> for Error {
+    fn from(e: GeohashError) -> Self {
+        match e {
+            GeohashError::InvalidHashCharacter(c) => {
+                Error::InvalidHash(format!("character {c:?} is not in the geohash alphabet"))
+            }
+            GeohashError::InvalidCoordinateRange(c) => {
+                Error::InvalidCoordinate { lng: c.x, lat: c.y }
+            }
+            // only `geohash::encode` reports a length, and there it is the
+            // precision argument; hashes are checked by `validate_geohash`
+            GeohashError::InvalidLength(precision) => Error::InvalidPrecision(precision),
+            GeohashError::InvalidHash(msg) => Error::InvalidHash(msg),
+        }
+    }
+}
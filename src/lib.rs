@@ -1,36 +1,28 @@
+use geo::orient::Direction;
 use geo::{
-    algorithm::centroid::Centroid, Area, BoundingRect, Contains, InteriorPoint, Intersects, Point,
-    Polygon, Rect,
+    algorithm::centroid::Centroid, unary_union, Area, BooleanOps, BoundingRect, Contains, Distance,
+    Euclidean, Geodesic, Geometry, Haversine, InteriorPoint, Intersects, Line, LineString,
+    MultiPolygon, Orient, Point, Polygon, Rect, Translate,
 };
 
-use geohash::{decode_bbox, encode, neighbors, GeohashError};
-use pyo3::prelude::*;
-use pyo3::types::PyAny;
-use pyo3::wrap_pyfunction;
+use geohash::{decode_bbox, encode, neighbors};
 use rayon::prelude::*;
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 
-// ── Helpers ──────────────────────────────────────────────────────────────────
+mod error;
+mod packed;
+#[cfg(feature = "python")]
+mod python;
 
-/// Build a custom thread pool, or return `None` to use the global Rayon pool.
-///
-/// Call this *before* releasing the GIL so that pool-creation errors can be
-/// converted to Python exceptions while we still hold it.
-fn make_pool(num_threads: Option<usize>) -> PyResult<Option<rayon::ThreadPool>> {
-    match num_threads {
-        None => Ok(None),
-        Some(n) => rayon::ThreadPoolBuilder::new()
-            .num_threads(n)
-            .build()
-            .map(Some)
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string())),
-    }
-}
+pub use error::Error;
+pub use packed::PackedGeohash;
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
 
 /// Run `f` on `pool`, or on the global Rayon pool if `pool` is `None`.
 ///
-/// Call this *inside* `py.allow_threads` so the GIL is released while Rayon
-/// workers are running.
+/// The Python bindings call this *inside* `py.allow_threads` so the GIL is
+/// released while Rayon workers are running.
 fn run_with_pool<F, T>(pool: &Option<rayon::ThreadPool>, f: F) -> T
 where
     F: FnOnce() -> T + Send,
@@ -42,9 +34,26 @@ where
     }
 }
 
-fn all_neighbors(hash: &str) -> Result<[String; 8], GeohashError> {
-    let nbrs = neighbors(hash)?;
-    Ok([nbrs.n, nbrs.ne, nbrs.e, nbrs.se, nbrs.s, nbrs.sw, nbrs.w, nbrs.nw])
+/// The up to 8 cells around `hash`, wrapping east-west across the antimeridian.
+///
+/// Cells come row by row from the south-west to the north-east corner. Unlike
+/// `geohash::neighbors`, nothing is returned beyond a pole: a cell in the top
+/// or bottom row only has 5 neighbours.
+pub fn all_neighbors(hash: &str) -> Result<Vec<String>, Error> {
+    let grid = CellGrid::of_hash(hash)?;
+    let (col, row) = grid.index_of(hash)?;
+    let mut nbrs = Vec::with_capacity(8);
+    for d_row in [-1, 0, 1] {
+        if !grid.has_row(row + d_row) {
+            continue;
+        }
+        for d_col in [-1, 0, 1] {
+            if (d_col, d_row) != (0, 0) {
+                nbrs.push(grid.hash_at(col + d_col, row + d_row)?);
+            }
+        }
+    }
+    Ok(nbrs)
 }
 
 /// BFS frontier expansion: expand a set of geohashes outward by `n_hops` steps.
@@ -52,7 +61,7 @@ fn all_neighbors(hash: &str) -> Result<[String; 8], GeohashError> {
 pub fn expand_geohash_set(
     geohashes: &HashSet<String>,
     n_hops: usize,
-) -> Result<HashSet<String>, GeohashError> {
+) -> Result<HashSet<String>, Error> {
     let mut all = geohashes.clone();
     // Initial frontier: input cells with at least one neighbor outside the set.
     // Neighbour lookup here validates user-provided hashes.
@@ -78,90 +87,1643 @@ pub fn expand_geohash_set(
     Ok(all)
 }
 
+/// Mean Earth radius, for the spherical reach computations of the expansion.
+const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+/// Metres per degree of longitude and of latitude at latitude `lat`, on the
+/// sphere.
+fn metres_per_degree(lat: f64) -> (f64, f64) {
+    let per_degree = 1f64.to_radians() * EARTH_RADIUS_M;
+    (per_degree * lat.to_radians().cos(), per_degree)
+}
+
+/// Largest longitude difference, in degrees, between two points on the parallel
+/// at `lat` that are at most `distance_m` apart; `None` once the whole parallel
+/// is within reach.
+fn parallel_reach_deg(lat: f64, distance_m: f64) -> Option<f64> {
+    let angle = distance_m / EARTH_RADIUS_M;
+    let (sin_lat, cos_lat) = lat.to_radians().sin_cos();
+    let cos_reach = (angle.cos() - sin_lat * sin_lat) / (cos_lat * cos_lat);
+    if angle >= std::f64::consts::PI || cos_reach.is_nan() || cos_reach <= -1.0 {
+        return None;
+    }
+    Some(cos_reach.min(1.0).acos().to_degrees())
+}
+
+/// Expand a set of geohashes outward by `expansion_m` metres.
+///
+/// Rows are reached north-south from the cell height; on each reached row, the
+/// east-west reach is the spherical one at the more poleward of the source and
+/// target rows, since paths bend towards the pole. Every point within
+/// `expansion_m` of a pole is reachable across it, so rows that close to a pole
+/// get all of their cells, and work stays bounded by the size of the result.
+/// Hashes of different precisions are each expanded on their own grid; a
+/// negative or non-finite distance adds nothing. Returns an error if any hash
+/// is malformed.
+pub fn expand_geohash_set_by_distance(
+    geohashes: &HashSet<String>,
+    expansion_m: f64,
+) -> Result<HashSet<String>, Error> {
+    let packed = geohashes
+        .iter()
+        .map(|h| h.parse())
+        .collect::<Result<HashSet<PackedGeohash>, Error>>()?;
+    let expanded = expand_packed_geohash_set_by_distance(&packed, expansion_m)?;
+    Ok(expanded.iter().map(PackedGeohash::to_string).collect())
+}
+
+/// `expand_geohash_set_by_distance` of packed geohashes. The expansion works on
+/// grid indices throughout; the string variant only parses and formats.
+pub fn expand_packed_geohash_set_by_distance(
+    geohashes: &HashSet<PackedGeohash>,
+    expansion_m: f64,
+) -> Result<HashSet<PackedGeohash>, Error> {
+    if !expansion_m.is_finite() || expansion_m <= 0.0 {
+        return Ok(geohashes.clone());
+    }
+    let mut by_precision: HashMap<usize, Vec<PackedGeohash>> = HashMap::new();
+    for h in geohashes {
+        by_precision.entry(h.precision()).or_default().push(*h);
+    }
+
+    let mut expanded = HashSet::new();
+    for (precision, hashes) in by_precision {
+        let grid = CellGrid::new(precision)?;
+        let (_, cell_h_m) = grid.cell_size_m(0.0);
+        let lat_hops = (expansion_m / cell_h_m).ceil() as i64;
+        // absolute latitude of the poleward edge of a row
+        let poleward_lat = |row: i64| {
+            let south = -90.0 + row as f64 * grid.cell_h;
+            south.abs().max((south + grid.cell_h).abs())
+        };
+        // rows counted from each pole that lie within reach across it
+        let (mut north_cap, mut south_cap) = (0i64, 0i64);
+
+        // Columns of the input cells by row, merged into runs of adjacent
+        // columns, so memory stays proportional to the input rather than to the
+        // rows each cell reaches.
+        let mut cols_by_row: HashMap<i64, Vec<i64>> = HashMap::new();
+        for h in hashes {
+            let (col, row) = h.index();
+            cols_by_row.entry(row).or_default().push(col);
+        }
+        let mut source_rows: Vec<(i64, Vec<(i64, i64)>)> = Vec::with_capacity(cols_by_row.len());
+        for (row, mut cols) in cols_by_row {
+            cols.sort_unstable();
+            let mut runs: Vec<(i64, i64)> = Vec::new();
+            for col in cols {
+                match runs.last_mut() {
+                    Some((_, last)) if col <= *last + 1 => *last = col,
+                    _ => runs.push((col, col)),
+                }
+            }
+            source_rows.push((row, runs));
+            let to_north_m = (grid.rows - 1 - row) as f64 * cell_h_m;
+            let to_south_m = row as f64 * cell_h_m;
+            north_cap = north_cap.max(((expansion_m - to_north_m) / cell_h_m).ceil() as i64);
+            south_cap = south_cap.max(((expansion_m - to_south_m) / cell_h_m).ceil() as i64);
+        }
+        source_rows.sort_unstable_by_key(|(row, _)| *row);
+        let mut full_rows: HashSet<i64> = (0..south_cap.min(grid.rows)).collect();
+        full_rows.extend((0..north_cap.min(grid.rows)).map(|r| grid.rows - 1 - r));
+
+        // North-south pass: the ranges of rows within reach of a source row.
+        let mut reached_rows: Vec<(i64, i64)> = Vec::new();
+        for (row, _) in &source_rows {
+            let (first, last) = ((row - lat_hops).max(0), (row + lat_hops).min(grid.rows - 1));
+            match reached_rows.last_mut() {
+                Some((_, end)) if first <= *end + 1 => *end = last,
+                _ => reached_rows.push((first, last)),
+            }
+        }
+
+        // East-west pass: on each reached row, merge the runs of the source rows
+        // within reach, each widened by the reach between the two rows.
+        let mut window = 0;
+        'rows: for row in reached_rows
+            .into_iter()
+            .flat_map(|(first, last)| first..=last)
+        {
+            if full_rows.contains(&row) {
+                continue;
+            }
+            while source_rows[window].0 < row - lat_hops {
+                window += 1;
+            }
+            let mut spans = Vec::new();
+            let sources = source_rows[window..]
+                .iter()
+                .take_while(|(source_row, _)| *source_row <= row + lat_hops);
+            for (source_row, runs) in sources {
+                let lat = poleward_lat(row).max(poleward_lat(*source_row));
+                let lon_hops = match parallel_reach_deg(lat, expansion_m) {
+                    Some(reach) => (reach / grid.cell_w).ceil(),
+                    None => f64::INFINITY,
+                };
+                if 2.0 * lon_hops + 1.0 >= grid.cols as f64 {
+                    full_rows.insert(row);
+                    continue 'rows;
+                }
+                let lon_hops = lon_hops as i64;
+                spans.extend(
+                    runs.iter()
+                        .map(|&(first, last)| (first - lon_hops, last + lon_hops)),
+                );
+            }
+            spans.sort_unstable();
+            let mut next_col = i64::MIN;
+            for (first, last) in spans {
+                for c in first.max(next_col)..=last {
+                    expanded.insert(grid.packed_at(c, row));
+                }
+                next_col = next_col.max(last + 1);
+            }
+        }
+        for row in full_rows {
+            for col in 0..grid.cols {
+                expanded.insert(grid.packed_at(col, row));
+            }
+        }
+    }
+    Ok(expanded)
+}
+
+/// Shortest distance on the sphere, in metres, between two cells.
+///
+/// Meridians are great circles, so the closest points of two lat/lng boxes are
+/// found from a corner of one of them: either straight along a meridian when
+/// their longitude bands overlap, or from an end of a facing edge otherwise.
+fn cell_to_cell_m(a: &Rect, b: &Rect) -> f64 {
+    let corners = |rect: &Rect| rect.to_polygon().exterior().points().collect::<Vec<_>>();
+    let from_a = corners(a)
+        .into_iter()
+        .map(|corner| Haversine.distance(corner, closest_point_in_cell(corner, b)));
+    let from_b = corners(b)
+        .into_iter()
+        .map(|corner| Haversine.distance(corner, closest_point_in_cell(corner, a)));
+    from_a.chain(from_b).fold(f64::INFINITY, f64::min)
+}
+
+/// Expand a set of geohashes to every cell within `expansion_m` metres of it.
+///
+/// Unlike `expand_geohash_set_by_distance`, which reaches a whole box of rows
+/// and columns around each cell, a cell is only kept when its nearest edge is
+/// within `expansion_m` of the nearest original cell, measured on the sphere, so
+/// the expansion is round rather than square. Returns an error if any hash is
+/// malformed.
+pub fn expand_geohash_set_exact(
+    geohashes: &HashSet<String>,
+    expansion_m: f64,
+) -> Result<HashSet<String>, Error> {
+    let mut expanded = geohashes.clone();
+    expanded.extend(expand_geohash_set_with_distance(geohashes, expansion_m)?.into_keys());
+    Ok(expanded)
+}
+
+/// How far a cell added by `expand_geohash_set_with_distance` lies from the
+/// original set.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExpansionLabel {
+    /// The ring of the breadth-first expansion the cell was reached in: 1 for
+    /// the cells touching the set, as with `expand_geohash_set`'s hops.
+    pub hops: usize,
+    /// Distance on the sphere, in metres, to the nearest original cell.
+    pub distance_m: f64,
+}
+
+/// The cells added by `expand_geohash_set_exact`, each labelled with its hop
+/// and its distance to the nearest original cell.
+///
+/// The set is expanded breadth-first from its edge, one ring of neighbours at
+/// a time as in `expand_geohash_set`. Each new ring is measured as it is
+/// reached, in parallel, against the nearest cells on the edge of the set
+/// only; cells further than `expansion_m` are dropped and not expanded from.
+/// Every cell within reach is still found: the cells along the shortest path
+/// to the set are all within reach themselves. Returns an error if any hash is
+/// malformed.
+pub fn expand_geohash_set_with_distance(
+    geohashes: &HashSet<String>,
+    expansion_m: f64,
+) -> Result<HashMap<String, ExpansionLabel>, Error> {
+    let mut by_precision: HashMap<usize, HashSet<&str>> = HashMap::new();
+    for h in geohashes {
+        validate_geohash(h)?;
+        by_precision.entry(h.len()).or_default().insert(h);
+    }
+    let mut labels = HashMap::new();
+    if !expansion_m.is_finite() || expansion_m <= 0.0 {
+        return Ok(labels);
+    }
+
+    for (precision, hashes) in by_precision {
+        let grid = CellGrid::new(precision)?;
+        let (_, cell_h_m) = grid.cell_size_m(0.0);
+        // Cells on the edge of the set, by row, with their columns sorted: the
+        // nearest original cell to an outside cell is always one of these.
+        let mut edge = Vec::new();
+        let mut edge_rows: HashMap<i64, Vec<i64>> = HashMap::new();
+        for h in &hashes {
+            let outside = |n: &String| !hashes.contains(n.as_str());
+            if all_neighbors(h)?.iter().any(outside) {
+                let (col, row) = grid.index_of(h)?;
+                edge_rows.entry(row).or_default().push(col);
+                edge.push(h.to_string());
+            }
+        }
+        for cols in edge_rows.values_mut() {
+            cols.sort_unstable();
+        }
+        let cell_rect = |col: i64, row: i64| -> Result<Rect, Error> {
+            Ok(decode_bbox(&grid.hash_at(col, row)?)?)
+        };
+        // Between two cells of one pair of rows, distance grows with the column
+        // gap, so only the nearest edge columns on either side are measured.
+        let nearest_m = |hash: &String| -> Result<Option<f64>, Error> {
+            let (col, row) = grid.index_of(hash)?;
+            let rect = decode_bbox(hash)?;
+            let mut nearest = f64::INFINITY;
+            for d_row in 0..grid.rows {
+                // rows further than this can't be any closer
+                if (d_row - 1).max(0) as f64 * cell_h_m > nearest.min(expansion_m) {
+                    break;
+                }
+                for edge_row in [row - d_row, row + d_row] {
+                    let Some(cols) = edge_rows.get(&edge_row) else {
+                        continue;
+                    };
+                    let next = cols.partition_point(|&c| c < col);
+                    let before = cols[(next + cols.len() - 1) % cols.len()];
+                    let after = cols[next % cols.len()];
+                    for edge_col in [before, after] {
+                        let d = cell_to_cell_m(&rect, &cell_rect(edge_col, edge_row)?);
+                        nearest = nearest.min(d);
+                    }
+                    if d_row == 0 {
+                        break;
+                    }
+                }
+            }
+            Ok((nearest <= expansion_m).then_some(nearest))
+        };
+
+        // cells measured so far, whether or not they were within reach
+        let mut visited: HashSet<String> = HashSet::new();
+        let mut frontier = edge;
+        for hops in 1.. {
+            let mut ring = HashSet::new();
+            for h in &frontier {
+                for n in all_neighbors(h)? {
+                    if !hashes.contains(n.as_str()) && !visited.contains(&n) {
+                        ring.insert(n);
+                    }
+                }
+            }
+            if ring.is_empty() {
+                break;
+            }
+            visited.extend(ring.iter().cloned());
+            let reached: Vec<Result<Option<(String, f64)>, Error>> = ring
+                .into_par_iter()
+                .map(|h| nearest_m(&h).map(|d| d.map(|d| (h, d))))
+                .collect();
+            frontier = Vec::new();
+            for r in reached {
+                if let Some((h, distance_m)) = r? {
+                    frontier.push(h.clone());
+                    labels.insert(h, ExpansionLabel { hops, distance_m });
+                }
+            }
+        }
+    }
+    Ok(labels)
+}
+
+/// Cells just outside a set: the neighbours of its members, at their own
+/// precision, that are not members themselves.
+fn outer_frontier(geohashes: &HashSet<String>) -> Result<HashSet<String>, Error> {
+    let mut frontier = HashSet::new();
+    for gh in geohashes {
+        for n in all_neighbors(gh)? {
+            if !geohashes.contains(&n) {
+                frontier.insert(n);
+            }
+        }
+    }
+    Ok(frontier)
+}
+
+/// Shrink a set of geohashes inward by `erosion_m` metres.
+///
+/// The inverse of `expand_geohash_set_by_distance`: the cells just outside the
+/// set are expanded by `erosion_m` and every cell they reach is dropped, so
+/// what remains is the core of the set, at least that far from its boundary.
+/// The poles and the antimeridian are not boundaries. Hashes of different
+/// precisions are each eroded on their own grid; a negative or non-finite
+/// distance removes nothing. Returns an error if any hash is malformed.
+pub fn erode_geohash_set_by_distance(
+    geohashes: &HashSet<String>,
+    erosion_m: f64,
+) -> Result<HashSet<String>, Error> {
+    let reached = expand_geohash_set_by_distance(&outer_frontier(geohashes)?, erosion_m)?;
+    Ok(geohashes.difference(&reached).cloned().collect())
+}
+
+/// Shrink a set of geohashes to the cells further than `erosion_m` metres from
+/// any cell outside it, measured on the sphere as in `expand_geohash_set_exact`.
+/// Returns an error if any hash is malformed.
+pub fn erode_geohash_set_exact(
+    geohashes: &HashSet<String>,
+    erosion_m: f64,
+) -> Result<HashSet<String>, Error> {
+    let reached = expand_geohash_set_exact(&outer_frontier(geohashes)?, erosion_m)?;
+    Ok(geohashes.difference(&reached).cloned().collect())
+}
+
+/// Whether `hash` or one of its ancestors is in `geohashes`.
+fn is_covered(geohashes: &HashSet<String>, hash: &str) -> bool {
+    (1..=hash.len()).any(|n| geohashes.contains(&hash[..n]))
+}
+
+/// Expand a mixed-precision set of geohashes at its finest precision.
+///
+/// `expand` is `expand_geohash_set_by_distance` or `expand_geohash_set_exact`,
+/// and is applied to the area the set covers as if every cell had been
+/// uncompacted to the finest precision. Only the cells along the edge of that
+/// area are uncompacted, so large interior cells cost nothing; the original
+/// cells are returned as they are, with the added cells at the finest
+/// precision. A set of a single precision is expanded directly. Returns an
+/// error if any hash is malformed.
+pub fn expand_mixed_geohash_set(
+    geohashes: &HashSet<String>,
+    expansion_m: f64,
+    expand: fn(&HashSet<String>, f64) -> Result<HashSet<String>, Error>,
+) -> Result<HashSet<String>, Error> {
+    geohashes.iter().try_for_each(|h| validate_geohash(h))?;
+    let finest = geohashes.iter().map(String::len).max().unwrap_or(0);
+    if geohashes.iter().all(|h| h.len() == finest) {
+        return expand(geohashes, expansion_m);
+    }
+
+    // A cell is on the edge unless each neighbour of its own size is covered
+    // whole. Edge cells are split level by level, keeping only the children
+    // that are on the edge themselves.
+    let mut edge = HashSet::new();
+    let mut pending: Vec<String> = geohashes.iter().cloned().collect();
+    while let Some(h) = pending.pop() {
+        if all_neighbors(&h)?.iter().all(|n| is_covered(geohashes, n)) {
+            continue;
+        }
+        if h.len() == finest {
+            edge.insert(h);
+        } else {
+            pending.extend(child_hashes(&h));
+        }
+    }
+    let mut expanded = geohashes.clone();
+    for h in expand(&edge, expansion_m)? {
+        if !is_covered(geohashes, &h) {
+            expanded.insert(h);
+        }
+    }
+    Ok(expanded)
+}
+
+// ── Cell grid ─────────────────────────────────────────────────────────────────
+
+/// The regular lng/lat grid formed by the geohash cells of one precision.
+///
+/// Columns run west to east from -180° and rows south to north from -90°, so
+/// cell arithmetic (neighbours, line walks, dilation) works on plain indices.
+struct CellGrid {
+    precision: usize,
+    cols: i64,
+    rows: i64,
+    cell_w: f64,
+    cell_h: f64,
+}
+
+impl CellGrid {
+    fn new(precision: usize) -> Result<Self, Error> {
+        if precision == 0 || precision > 12 {
+            return Err(Error::InvalidPrecision(precision));
+        }
+        let bits = 5 * precision as u32;
+        let (cols, rows) = (1i64 << (bits - bits / 2), 1i64 << (bits / 2));
+        Ok(CellGrid {
+            precision,
+            cols,
+            rows,
+            cell_w: 360.0 / cols as f64,
+            cell_h: 180.0 / rows as f64,
+        })
+    }
+
+    /// The grid of `hash`'s precision. A hash of the wrong length is an
+    /// `Error::InvalidHash`, not an `Error::InvalidPrecision`.
+    fn of_hash(hash: &str) -> Result<Self, Error> {
+        validate_geohash(hash)?;
+        CellGrid::new(hash.len())
+    }
+
+    fn col_of(&self, x: f64) -> i64 {
+        (((x + 180.0) / self.cell_w).floor() as i64).clamp(0, self.cols - 1)
+    }
+
+    fn row_of(&self, y: f64) -> i64 {
+        (((y + 90.0) / self.cell_h).floor() as i64).clamp(0, self.rows - 1)
+    }
+
+    fn has_row(&self, row: i64) -> bool {
+        (0..self.rows).contains(&row)
+    }
+
+    /// Latitude of the centre of `row`.
+    fn row_lat(&self, row: i64) -> f64 {
+        -90.0 + (row as f64 + 0.5) * self.cell_h
+    }
+
+    /// Width and height in metres of a cell at latitude `lat`, on the sphere.
+    fn cell_size_m(&self, lat: f64) -> (f64, f64) {
+        let (m_per_lng, m_per_lat) = metres_per_degree(lat);
+        (self.cell_w * m_per_lng, self.cell_h * m_per_lat)
+    }
+
+    /// Column and row of a geohash of this grid's precision.
+    fn index_of(&self, hash: &str) -> Result<(i64, i64), Error> {
+        validate_geohash(hash)?;
+        let center = decode_bbox(hash)?.center();
+        Ok((self.col_of(center.x), self.row_of(center.y)))
+    }
+
+    /// Geohash of the cell at `col` (taken modulo the ring) and `row`.
+    fn hash_at(&self, col: i64, row: i64) -> Result<String, Error> {
+        let center = geo_types::Coord {
+            x: -180.0 + (col.rem_euclid(self.cols) as f64 + 0.5) * self.cell_w,
+            y: self.row_lat(row),
+        };
+        Ok(encode(center, self.precision)?)
+    }
+
+    /// `hash_at` as a packed geohash: the column wraps, the row must be on the
+    /// grid.
+    fn packed_at(&self, col: i64, row: i64) -> PackedGeohash {
+        PackedGeohash::from_index(col.rem_euclid(self.cols), row, self.precision)
+    }
+
+    /// `all_neighbors` of a packed geohash of this grid's precision.
+    fn packed_neighbors(&self, hash: PackedGeohash) -> impl Iterator<Item = PackedGeohash> + '_ {
+        let (col, row) = hash.index();
+        (-1..=1)
+            .filter(move |d_row| self.has_row(row + d_row))
+            .flat_map(move |d_row| (-1..=1).map(move |d_col| (d_col, d_row)))
+            .filter(|&offset| offset != (0, 0))
+            .map(move |(d_col, d_row)| self.packed_at(col + d_col, row + d_row))
+    }
+}
+
+// ── Antimeridian ──────────────────────────────────────────────────────────────
+
+/// Make a run of coordinates continuous in longitude.
+///
+/// A step of more than 180° between consecutive coordinates is read as a
+/// crossing of the antimeridian (the GeoJSON convention), so the rest of the run
+/// is shifted by ±360°. A full 360° step, from -180 to 180, is kept as it is:
+/// that is how bands around the whole globe are usually drawn. The result may
+/// leave the [-180, 180] range.
+fn unwrap_longitudes(coords: &[geo_types::Coord]) -> Vec<geo_types::Coord> {
+    let mut offset = 0.0;
+    let mut previous_x = coords.first().map_or(0.0, |c| c.x);
+    coords
+        .iter()
+        .map(|c| {
+            let step = c.x - previous_x;
+            if step > 180.0 && step < 360.0 {
+                offset -= 360.0;
+            } else if step < -180.0 && step > -360.0 {
+                offset += 360.0;
+            }
+            previous_x = c.x;
+            geo_types::Coord {
+                x: c.x + offset,
+                y: c.y,
+            }
+        })
+        .collect()
+}
+
+/// Index of the 360°-wide longitude window `[-180 + 360k, 180 + 360k)` holding `x`.
+fn longitude_window(x: f64) -> f64 {
+    ((x + 180.0) / 360.0).floor()
+}
+
+/// `polygon` in continuous longitudes, starting within [-180, 180), or `None`
+/// when it already lies strictly inside that range.
+///
+/// A ring going all the way around a pole, as polar caps often are, is closed
+/// through that pole.
+fn unwrap_polygon(polygon: &Polygon) -> Option<Polygon> {
+    let mut exterior = unwrap_longitudes(&polygon.exterior().0);
+    let (&first, &last) = (exterior.first()?, exterior.last()?);
+    if (last.x - first.x).abs() > 180.0 {
+        // close the ring through the pole on the side it lies on
+        let mean_lat = exterior.iter().map(|c| c.y).sum::<f64>() / exterior.len() as f64;
+        let pole = if mean_lat >= 0.0 { 90.0 } else { -90.0 };
+        exterior.push((last.x, pole).into());
+        exterior.push((first.x, pole).into());
+        exterior.push(first);
+    }
+    let exterior = LineString::new(exterior);
+    let bbox = exterior.bounding_rect()?;
+    if bbox.min().x > -180.0 && bbox.max().x < 180.0 {
+        return None;
+    }
+
+    // Holes are unwrapped on their own, then moved next to the exterior.
+    let center_x = bbox.center().x;
+    let holes = polygon
+        .interiors()
+        .iter()
+        .map(|hole| {
+            let mut coords = unwrap_longitudes(&hole.0);
+            let hole_x = coords.first().map_or(center_x, |c| c.x);
+            let shift = ((center_x - hole_x) / 360.0).round() * 360.0;
+            coords.iter_mut().for_each(|c| c.x += shift);
+            LineString::new(coords)
+        })
+        .collect();
+    let shift = 360.0 * longitude_window(bbox.min().x);
+    // the union below reads holes from their winding
+    let unwrapped = Polygon::new(exterior, holes).orient(Direction::Default);
+    Some(unwrapped.translate(-shift, 0.0))
+}
+
+/// A polygon laid out on the geohash grid.
+///
+/// Cells only exist within [-180, 180], so the walk runs over `pieces`; cells
+/// are tested against `whole`, which has no edge along the antimeridian, so
+/// cells next to it are not mistaken for boundary cells.
+struct GridPolygon {
+    whole: Polygon,
+    /// Parts of `whole` within [-180, 180]; empty when that is all of it.
+    pieces: Vec<Polygon>,
+}
+
+impl GridPolygon {
+    /// Polygons whose cells to walk.
+    fn walk_targets(&self) -> &[Polygon] {
+        if self.pieces.is_empty() {
+            std::slice::from_ref(&self.whole)
+        } else {
+            &self.pieces
+        }
+    }
+}
+
+/// Lay `polygon` out on the grid, splitting it if it reaches the antimeridian.
+///
+/// The unwrapped polygon is repeated every 360° and dissolved, so that parts
+/// meeting across the antimeridian become one `whole`; the pieces are the
+/// parts of it within [-180, 180].
+fn grid_polygons(polygon: Polygon) -> Vec<GridPolygon> {
+    let Some(unwrapped) = unwrap_polygon(&polygon) else {
+        return vec![GridPolygon {
+            whole: polygon,
+            pieces: vec![],
+        }];
+    };
+    let copies = [
+        unwrapped.translate(-360.0, 0.0),
+        unwrapped.translate(360.0, 0.0),
+        unwrapped,
+    ];
+    let periodic = unary_union(&copies);
+    let window = Rect::new((-180.0, -90.0), (180.0, 90.0)).to_polygon();
+    let pieces = periodic.intersection(&window);
+    periodic
+        .into_iter()
+        .filter_map(|whole| {
+            let own: Vec<Polygon> = pieces
+                .iter()
+                .filter(|piece| piece.interior_point().is_some_and(|p| whole.intersects(&p)))
+                .cloned()
+                .collect();
+            (!own.is_empty()).then_some(GridPolygon { whole, pieces: own })
+        })
+        .collect()
+}
+
+/// Split a polygon crossing the antimeridian into pieces within [-180, 180].
+///
+/// Accepts both wrapped rings (jumping from 179 to -179) and continuous ones
+/// (going from 179 to 181). A ring going all the way around a pole, as polar
+/// caps often are, is closed through that pole first. Polygons that don't
+/// reach the antimeridian come back unchanged.
+pub fn split_antimeridian(polygon: &Polygon) -> Vec<Polygon> {
+    grid_polygons(polygon.clone())
+        .into_iter()
+        .flat_map(|part| {
+            if part.pieces.is_empty() {
+                vec![part.whole]
+            } else {
+                part.pieces
+            }
+        })
+        .collect()
+}
+
+/// Split a segment in continuous longitudes into pieces within [-180, 180].
+fn split_segment_antimeridian(segment: Line) -> Vec<Line> {
+    let (a, b) = (segment.start, segment.end);
+    let (window_a, window_b) = (longitude_window(a.x), longitude_window(b.x));
+    let crossing = |edge_x: f64| geo_types::Coord {
+        x: edge_x,
+        y: a.y + (edge_x - a.x) / (b.x - a.x) * (b.y - a.y),
+    };
+    let edges = (window_a.min(window_b) as i64..window_a.max(window_b) as i64)
+        .map(|k| crossing(180.0 + 360.0 * k as f64));
+    let mut cuts = vec![a];
+    if window_a < window_b {
+        cuts.extend(edges);
+    } else {
+        cuts.extend(edges.rev());
+    }
+    cuts.push(b);
+    cuts.windows(2)
+        .map(|pair| {
+            // shift by the window of the midpoint so a piece ending exactly on
+            // the antimeridian stays on its own side
+            let shift = 360.0 * longitude_window((pair[0].x + pair[1].x) / 2.0);
+            Line::new(pair[0], pair[1]).translate(-shift, 0.0)
+        })
+        .collect()
+}
+
 // ── Polygon → geohash (existing) ─────────────────────────────────────────────
 
-pub fn polygons_to_geohashes<PI>(
+/// Whether a cell already known to intersect `polygon` lies fully inside it.
+fn cell_within_polygon(polygon: &Polygon, cell: &Polygon) -> bool {
+    if !polygon.interiors().is_empty() {
+        // robust path when holes exist
+        polygon.contains(cell)
+    } else {
+        // fast path for hole-free polygons (strict containment)
+        !polygon.exterior().intersects(cell.exterior())
+            && cell.unsigned_area() <= polygon.unsigned_area()
+    }
+}
+
+/// Breadth-first flood fill over geohash cells, starting from `seed_geohashes`,
+/// which all share one precision.
+///
+/// `visit` is called exactly once per reached cell with its hash and bounding
+/// box, and returns whether the cell touches the shape being covered; the walk
+/// only expands from cells that do, and stops at the first error. Cells are
+/// packed, so the walk allocates no strings.
+fn flood_fill_cells<I, F>(seed_geohashes: I, mut visit: F) -> Result<(), Error>
+where
+    I: IntoIterator<Item = PackedGeohash>,
+    F: FnMut(PackedGeohash, Rect) -> Result<bool, Error>,
+{
+    let mut visited_geohashes = HashSet::new();
+    let mut testing_geohashes: VecDeque<PackedGeohash> = seed_geohashes.into_iter().collect();
+    let Some(seed) = testing_geohashes.front() else {
+        return Ok(());
+    };
+    let grid = CellGrid::new(seed.precision())?;
+
+    while let Some(current_geohash) = testing_geohashes.pop_front() {
+        if !visited_geohashes.insert(current_geohash) {
+            continue;
+        }
+
+        // prune cells outside the shape early and don't expand from them
+        if !visit(current_geohash, current_geohash.bbox())? {
+            continue;
+        }
+
+        for neighbor in grid.packed_neighbors(current_geohash) {
+            if !visited_geohashes.contains(&neighbor) {
+                testing_geohashes.push_back(neighbor);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Flood fill over the geohash cells of `polygon` at `precision`.
+///
+/// The walk starts from the cell holding an interior seed and only expands from
+/// cells that intersect the polygon. `visit` is called exactly once per
+/// intersecting cell, with the cell's hash and its bounding polygon; an error
+/// from it ends the walk.
+fn walk_polygon_cells<F>(polygon: &Polygon, precision: usize, mut visit: F) -> Result<(), Error>
+where
+    F: FnMut(PackedGeohash, &Polygon) -> Result<(), Error>,
+{
+    // choose a seed inside the polygon
+    let Some(seed_point) = seed_interior_point_fast(polygon) else {
+        return Ok(()); // degenerate polygon, skip
+    };
+    let seed_gh = PackedGeohash::encode((seed_point.x(), seed_point.y()).into(), precision)?;
+
+    flood_fill_cells([seed_gh], |current_geohash, gh_bbox| {
+        let current_geohash_polygon = gh_bbox.to_polygon();
+        if !polygon.intersects(&current_geohash_polygon) {
+            return Ok(false);
+        }
+        visit(current_geohash, &current_geohash_polygon)?;
+        Ok(true)
+    })
+}
+
+/// Rule deciding whether a cell that touches a polygon belongs to its coverage.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CoverageMode {
+    /// The cell lies fully inside the polygon.
+    Contained,
+    /// The cell intersects the polygon at all.
+    Intersects,
+    /// The centre of the cell lies inside the polygon.
+    Centroid,
+    /// At least this fraction (0..=1) of the cell's area lies inside the polygon.
+    MinOverlap(f64),
+}
+
+impl From<bool> for CoverageMode {
+    /// Map the legacy `inner` flag: `true` is `Contained`, `false` is `Intersects`.
+    fn from(fully_contained_only: bool) -> Self {
+        if fully_contained_only {
+            CoverageMode::Contained
+        } else {
+            CoverageMode::Intersects
+        }
+    }
+}
+
+impl CoverageMode {
+    /// Whether a cell already known to intersect `polygon` is accepted.
+    ///
+    /// `MinOverlap` is judged on this polygon alone; the coverage walk adds up
+    /// the overlaps of a cell with every polygon of a multipolygon instead.
+    fn accepts(self, polygon: &Polygon, cell: &Polygon) -> bool {
+        match self {
+            CoverageMode::Contained => cell_within_polygon(polygon, cell),
+            // intersecting is enough
+            CoverageMode::Intersects => true,
+            CoverageMode::Centroid => cell
+                .bounding_rect()
+                .is_some_and(|rect| polygon.intersects(&rect.center())),
+            CoverageMode::MinOverlap(fraction) => cell_overlap_fraction(polygon, cell) >= fraction,
+        }
+    }
+}
+
+/// Cover `polygons` with the geohash cells at `precision` selected by `mode`.
+///
+/// `mode` accepts a `CoverageMode` or, for the classic behaviour, a bool
+/// (`true` keeps fully contained cells only, `false` every intersecting cell).
+/// Polygons reaching the antimeridian are covered continuously across it (see
+/// `split_antimeridian`).
+pub fn polygons_to_geohashes<PI, M>(
     polygons: PI,
     precision: usize,
-    fully_contained_only: bool,
-) -> Result<HashSet<String>, GeohashError>
+    mode: M,
+) -> Result<HashSet<String>, Error>
+where
+    PI: IntoIterator<Item = Polygon>,
+    M: Into<CoverageMode>,
+{
+    polygons_to_geohashes_bounded(polygons, precision, mode, None, &mut || false)
+}
+
+/// Number of cells walked between two calls to the `interrupted` hook.
+const INTERRUPT_CHECK_INTERVAL: usize = 4096;
+
+/// `polygons_to_geohashes` with a cell budget and a cancellation hook.
+///
+/// Fails with `Error::TooManyCells` as soon as more than `max_cells` cells are
+/// accepted, before the set can grow any further. `interrupted` is polled every
+/// few thousand cells walked; when it returns `true` the walk stops with
+/// `Error::Interrupted`.
+pub fn polygons_to_geohashes_bounded<PI, M>(
+    polygons: PI,
+    precision: usize,
+    mode: M,
+    max_cells: Option<usize>,
+    interrupted: &mut dyn FnMut() -> bool,
+) -> Result<HashSet<String>, Error>
+where
+    PI: IntoIterator<Item = Polygon>,
+    M: Into<CoverageMode>,
+{
+    let packed =
+        polygons_to_packed_geohashes_bounded(polygons, precision, mode, max_cells, interrupted)?;
+    Ok(packed.iter().map(PackedGeohash::to_string).collect())
+}
+
+/// `polygons_to_geohashes`, returning packed geohashes.
+pub fn polygons_to_packed_geohashes<PI, M>(
+    polygons: PI,
+    precision: usize,
+    mode: M,
+) -> Result<HashSet<PackedGeohash>, Error>
 where
     PI: IntoIterator<Item = Polygon>,
+    M: Into<CoverageMode>,
 {
+    polygons_to_packed_geohashes_bounded(polygons, precision, mode, None, &mut || false)
+}
+
+/// `polygons_to_geohashes_bounded`, returning packed geohashes. The walk works
+/// on packed cells throughout; the string variants only format the result.
+pub fn polygons_to_packed_geohashes_bounded<PI, M>(
+    polygons: PI,
+    precision: usize,
+    mode: M,
+    max_cells: Option<usize>,
+    interrupted: &mut dyn FnMut() -> bool,
+) -> Result<HashSet<PackedGeohash>, Error>
+where
+    PI: IntoIterator<Item = Polygon>,
+    M: Into<CoverageMode>,
+{
+    let mode = mode.into();
+    if let CoverageMode::MinOverlap(fraction) = mode {
+        if !(0.0..=1.0).contains(&fraction) {
+            return Err(Error::InvalidArgument(format!(
+                "min_overlap must be between 0 and 1, got {fraction}"
+            )));
+        }
+    }
     let mut accepted_geohashes = HashSet::new();
+    // MinOverlap is measured against the whole multipolygon: its polygons are
+    // disjoint, so the overlaps of a cell with each of them add up.
+    let mut overlaps: HashMap<PackedGeohash, f64> = HashMap::new();
+    let mut walked = 0usize;
+
+    // Each polygon gets its own walk: a cell rejected by one polygon in a
+    // multipolygon must still be tested against the others.
+    for part in polygons.into_iter().flat_map(grid_polygons) {
+        // a cell reached from two pieces of one polygon only counts once
+        let mut weighed = HashSet::new();
+        for piece in part.walk_targets() {
+            walk_polygon_cells(piece, precision, |current_geohash, cell_polygon| {
+                poll_interrupted(&mut walked, interrupted)?;
+                if accepted_geohashes.contains(&current_geohash) {
+                    return Ok(());
+                }
+                let accepted = match mode {
+                    CoverageMode::MinOverlap(fraction) => {
+                        if !weighed.insert(current_geohash) {
+                            return Ok(());
+                        }
+                        let overlap = overlaps.entry(current_geohash).or_insert(0.0);
+                        *overlap += cell_overlap_fraction(&part.whole, cell_polygon);
+                        *overlap >= fraction
+                    }
+                    _ => mode.accepts(&part.whole, cell_polygon),
+                };
+                if accepted {
+                    accepted_geohashes.insert(current_geohash);
+                    check_max_cells(accepted_geohashes.len(), max_cells)?;
+                }
+                Ok(())
+            })?;
+        }
+    }
+    Ok(accepted_geohashes)
+}
 
-    for polygon in polygons {
-        // Reset per polygon: a cell rejected by one polygon in a multipolygon
-        // must still be tested against the others.
-        let mut rejected_geohashes = HashSet::new();
-        let polygon_exterior = polygon.exterior();
-        let has_holes = !polygon.interiors().is_empty();
-
-        // choose a seed inside the polygon
-        let Some(seed_point) = seed_interior_point_fast(&polygon) else {
-            continue; // degenerate polygon, skip
-        };
+/// Count one more cell walked, polling `interrupted` every
+/// `INTERRUPT_CHECK_INTERVAL` cells.
+fn poll_interrupted(
+    walked: &mut usize,
+    interrupted: &mut dyn FnMut() -> bool,
+) -> Result<(), Error> {
+    *walked += 1;
+    if walked.is_multiple_of(INTERRUPT_CHECK_INTERVAL) && interrupted() {
+        return Err(Error::Interrupted);
+    }
+    Ok(())
+}
 
-        // convert to geohash and start BFS
-        let mut testing_geohashes = VecDeque::new();
-        let seed_gh = encode((seed_point.x(), seed_point.y()).into(), precision)?;
-        testing_geohashes.push_back(seed_gh);
+/// Fail with `Error::TooManyCells` if `count` is over `max_cells`.
+fn check_max_cells(count: usize, max_cells: Option<usize>) -> Result<(), Error> {
+    match max_cells {
+        Some(limit) if count > limit => Err(Error::TooManyCells { limit }),
+        _ => Ok(()),
+    }
+}
 
-        while let Some(current_geohash) = testing_geohashes.pop_front() {
-            if accepted_geohashes.contains(&current_geohash)
-                || rejected_geohashes.contains(&current_geohash)
-            {
-                continue;
+// ── Polygon → weighted geohash ────────────────────────────────────────────────
+
+/// Fraction of `cell`'s area inside `polygon`, for a cell known to intersect it.
+fn cell_overlap_fraction(polygon: &Polygon, cell: &Polygon) -> f64 {
+    if cell_within_polygon(polygon, cell) {
+        return 1.0;
+    }
+    (polygon.intersection(cell).unsigned_area() / cell.unsigned_area()).clamp(0.0, 1.0)
+}
+
+/// Cover `polygons` at `precision`, pairing each cell with the fraction of its
+/// area (0..1] that lies inside the geometry.
+///
+/// Interior cells get 1.0 straight away; the polygon/cell intersection is only
+/// computed for boundary cells. Cells that merely touch the boundary (zero
+/// overlap) are left out.
+pub fn polygons_to_geohash_weights<PI>(
+    polygons: PI,
+    precision: usize,
+) -> Result<HashMap<String, f64>, Error>
+where
+    PI: IntoIterator<Item = Polygon>,
+{
+    let mut weights: HashMap<String, f64> = HashMap::new();
+    for part in polygons.into_iter().flat_map(grid_polygons) {
+        // a cell reached from two pieces of one polygon only counts once
+        let mut weighed = HashSet::new();
+        for piece in part.walk_targets() {
+            walk_polygon_cells(piece, precision, |current_geohash, cell_polygon| {
+                if !weighed.insert(current_geohash) {
+                    return Ok(());
+                }
+                let fraction = cell_overlap_fraction(&part.whole, cell_polygon);
+                if fraction > 0.0 {
+                    // Polygons of a multipolygon are disjoint, so shared boundary
+                    // cells add up their parts.
+                    let w = weights.entry(current_geohash.to_string()).or_insert(0.0);
+                    *w = (*w + fraction).min(1.0);
+                }
+                Ok(())
+            })?;
+        }
+    }
+    Ok(weights)
+}
+
+/// Parallel Rust core of `polygons_to_geohash_mapping`, without PyO3 overhead.
+///
+/// Each group of polygons (one geography) is covered independently on `pool`;
+/// `result[i]` is the coverage of `groups[i]`.
+pub fn polygon_groups_to_geohashes(
+    groups: Vec<Vec<Polygon>>,
+    precision: usize,
+    fully_contained_only: bool,
+    pool: &Option<rayon::ThreadPool>,
+) -> Vec<Result<HashSet<String>, Error>> {
+    run_with_pool(pool, || {
+        groups
+            .into_par_iter()
+            .map(|polygons| polygons_to_geohashes(polygons, precision, fully_contained_only))
+            .collect()
+    })
+}
+
+// ── Circle → geohash ──────────────────────────────────────────────────────────
+
+/// Point of `rect` closest to `center` on the sphere.
+///
+/// Inside the cell's longitude band the closest point shares `center`'s
+/// longitude; otherwise it lies on the nearer meridian edge, at the latitude
+/// where that meridian passes closest to `center`.
+fn closest_point_in_cell(center: Point, rect: &Rect) -> Point {
+    let (min, max) = (rect.min(), rect.max());
+    let width = max.x - min.x;
+    // Longitude of `center` east of the cell's west edge, wrapped to [0, 360).
+    let east_of_min = (center.x() - min.x).rem_euclid(360.0);
+    if east_of_min <= width {
+        return Point::new(center.x(), center.y().clamp(min.y, max.y));
+    }
+    let (edge_x, dlng) = if east_of_min - width < 360.0 - east_of_min {
+        (max.x, east_of_min - width)
+    } else {
+        (min.x, 360.0 - east_of_min)
+    };
+    let cos_dlng = dlng.to_radians().cos();
+    let lat = if cos_dlng > 0.0 {
+        let tan_lat = center.y().to_radians().tan() / cos_dlng;
+        tan_lat.atan().to_degrees()
+    } else {
+        // a quarter turn away or more: the meridian is closest at the pole
+        90f64.copysign(center.y())
+    };
+    Point::new(edge_x, lat.clamp(min.y, max.y))
+}
+
+/// Cover the geodesic circle of `radius_m` metres around `center` at `precision`.
+///
+/// Uses the same flood fill as `polygons_to_geohashes`, but cells are tested with
+/// geodesic distances on the WGS84 ellipsoid, so the circle stays round at any
+/// latitude. With `fully_contained_only` a cell is kept when all its corners lie
+/// within the radius, otherwise whenever the circle touches it. A negative or
+/// non-finite radius is an `Error::InvalidArgument`.
+pub fn circle_to_geohashes(
+    center: Point,
+    radius_m: f64,
+    precision: usize,
+    fully_contained_only: bool,
+) -> Result<HashSet<String>, Error> {
+    circle_to_geohashes_bounded(
+        center,
+        radius_m,
+        precision,
+        fully_contained_only,
+        None,
+        &mut || false,
+    )
+}
+
+/// `circle_to_geohashes` with a cell budget and a cancellation hook, as in
+/// `polygons_to_geohashes_bounded`.
+pub fn circle_to_geohashes_bounded(
+    center: Point,
+    radius_m: f64,
+    precision: usize,
+    fully_contained_only: bool,
+    max_cells: Option<usize>,
+    interrupted: &mut dyn FnMut() -> bool,
+) -> Result<HashSet<String>, Error> {
+    if !radius_m.is_finite() || radius_m < 0.0 {
+        return Err(Error::InvalidArgument(format!(
+            "radius_m must be a finite non-negative number, got {radius_m}"
+        )));
+    }
+    let mut accepted_geohashes = HashSet::new();
+    let mut walked = 0usize;
+    let seed_gh = PackedGeohash::encode(center.into(), precision)?;
+
+    flood_fill_cells([seed_gh], |current_geohash, gh_bbox| {
+        poll_interrupted(&mut walked, interrupted)?;
+        let closest = closest_point_in_cell(center, &gh_bbox);
+        if Geodesic.distance(center, closest) > radius_m {
+            return Ok(false);
+        }
+        let accept = !fully_contained_only
+            || gh_bbox
+                .to_polygon()
+                .exterior()
+                .points()
+                .all(|corner| Geodesic.distance(center, corner) <= radius_m);
+        if accept {
+            accepted_geohashes.insert(current_geohash.to_string());
+            check_max_cells(accepted_geohashes.len(), max_cells)?;
+        }
+        Ok(true)
+    })?;
+    Ok(accepted_geohashes)
+}
+
+/// Parallel Rust core of `circles_to_geohashes`, without PyO3 overhead.
+///
+/// `circles` holds `(center, radius_m)` pairs; `result[i]` covers `circles[i]`.
+pub fn circles_to_geohashes(
+    circles: Vec<(Point, f64)>,
+    precision: usize,
+    fully_contained_only: bool,
+    pool: &Option<rayon::ThreadPool>,
+) -> Vec<Result<HashSet<String>, Error>> {
+    run_with_pool(pool, || {
+        circles
+            .into_par_iter()
+            .map(|(center, radius_m)| {
+                circle_to_geohashes(center, radius_m, precision, fully_contained_only)
+            })
+            .collect()
+    })
+}
+
+// ── LineString → geohash ──────────────────────────────────────────────────────
+
+/// Walk the cells crossed by `segment` one at a time, in order from its start.
+///
+/// Cells form a regular lng/lat grid at a given precision, so this is a plain
+/// grid traversal (Amanatides & Woo) over column/row indices; `visit` receives
+/// every crossed cell. A segment passing exactly through a cell
+/// corner also visits the two cells sharing that corner.
+fn walk_segment_cells<F>(segment: Line, precision: usize, mut visit: F) -> Result<(), Error>
+where
+    F: FnMut(PackedGeohash),
+{
+    let grid = CellGrid::new(precision)?;
+    for c in [segment.start, segment.end] {
+        if !(-180.0..=180.0).contains(&c.x) || !(-90.0..=90.0).contains(&c.y) {
+            return Err(Error::InvalidCoordinate { lng: c.x, lat: c.y });
+        }
+    }
+    let (cell_w, cell_h) = (grid.cell_w, grid.cell_h);
+
+    let (start, end) = (segment.start, segment.end);
+    let (mut col, mut row) = (grid.col_of(start.x), grid.row_of(start.y));
+    let (end_col, end_row) = (grid.col_of(end.x), grid.row_of(end.y));
+    let (dx, dy) = (end.x - start.x, end.y - start.y);
+    let (step_col, step_row) = (dx.signum() as i64, dy.signum() as i64);
+
+    // Parametric position along the segment (0..1) of the next column / row
+    // boundary, and the parametric length of one cell in each direction.
+    let next_boundary = |index: i64, step: i64, size: f64, origin: f64, from: f64, delta: f64| {
+        if delta == 0.0 {
+            f64::INFINITY
+        } else {
+            let edge = origin + (index + i64::from(step > 0)) as f64 * size;
+            (edge - from) / delta
+        }
+    };
+    let mut t_col = next_boundary(col, step_col, cell_w, -180.0, start.x, dx);
+    let mut t_row = next_boundary(row, step_row, cell_h, -90.0, start.y, dy);
+    let (dt_col, dt_row) = (cell_w / dx.abs(), cell_h / dy.abs());
+
+    visit(grid.packed_at(col, row));
+    while (col, row) != (end_col, end_row) {
+        // Once a column or row matches the end cell, only step along the other
+        // axis, so rounding can never walk past the end.
+        let move_col = row == end_row || (col != end_col && t_col <= t_row);
+        let move_row = col == end_col || (row != end_row && t_row <= t_col);
+        if move_col && move_row {
+            visit(grid.packed_at(col + step_col, row));
+            visit(grid.packed_at(col, row + step_row));
+        }
+        if move_col {
+            col += step_col;
+            t_col += dt_col;
+        }
+        if move_row {
+            row += step_row;
+            t_row += dt_row;
+        }
+        visit(grid.packed_at(col, row));
+    }
+    Ok(())
+}
+
+/// Shortest distance in metres between `segment` and `rect`, both in lng/lat.
+///
+/// Both are projected onto a local equirectangular plane at the cell's latitude,
+/// scaled by `metres_per_degree` as the cell sizes of `CellGrid` are.
+fn segment_to_cell_m(segment: Line, rect: &Rect) -> f64 {
+    let lat_center = (rect.min().y + rect.max().y) / 2.0;
+    let (kx, ky) = metres_per_degree(lat_center);
+    let project = |c: geo_types::Coord| geo_types::Coord {
+        x: c.x * kx,
+        y: c.y * ky,
+    };
+    let line = Line::new(project(segment.start), project(segment.end));
+    let cell = Rect::new(project(rect.min()), project(rect.max())).to_polygon();
+    Euclidean.distance(&line, &cell)
+}
+
+/// Geohash cells at `precision` that `lines` pass through.
+///
+/// Each segment is walked cell by cell rather than buffered, so the result is
+/// exactly the chain of crossed cells. With a positive `buffer_m`, every cell
+/// within that many metres of a line is added too, giving a corridor; it is
+/// grown by flood fill from the crossed cells of each segment.
+pub fn linestrings_to_geohashes<LI>(
+    lines: LI,
+    precision: usize,
+    buffer_m: f64,
+) -> Result<HashSet<String>, Error>
+where
+    LI: IntoIterator<Item = LineString>,
+{
+    linestrings_to_geohashes_bounded(lines, precision, buffer_m, None, &mut || false)
+}
+
+/// `linestrings_to_geohashes` with a cell budget and a cancellation hook, as in
+/// `polygons_to_geohashes_bounded`.
+pub fn linestrings_to_geohashes_bounded<LI>(
+    lines: LI,
+    precision: usize,
+    buffer_m: f64,
+    max_cells: Option<usize>,
+    interrupted: &mut dyn FnMut() -> bool,
+) -> Result<HashSet<String>, Error>
+where
+    LI: IntoIterator<Item = LineString>,
+{
+    let mut accepted_geohashes = HashSet::new();
+    let mut walked = 0usize;
+    for line in lines {
+        if let [only] = line.0.as_slice() {
+            // a single-coordinate line still occupies one cell
+            accepted_geohashes.insert(encode(*only, precision)?);
+            check_max_cells(accepted_geohashes.len(), max_cells)?;
+            continue;
+        }
+        let line = LineString::new(unwrap_longitudes(&line.0));
+        for segment in line.lines().flat_map(split_segment_antimeridian) {
+            let mut crossed = Vec::new();
+            walk_segment_cells(segment, precision, |hash| crossed.push(hash))?;
+            if buffer_m > 0.0 {
+                flood_fill_cells(crossed, |current_geohash, gh_bbox| {
+                    poll_interrupted(&mut walked, interrupted)?;
+                    if segment_to_cell_m(segment, &gh_bbox) > buffer_m {
+                        return Ok(false);
+                    }
+                    accepted_geohashes.insert(current_geohash.to_string());
+                    check_max_cells(accepted_geohashes.len(), max_cells)?;
+                    Ok(true)
+                })?;
+            } else {
+                for hash in crossed {
+                    poll_interrupted(&mut walked, interrupted)?;
+                    accepted_geohashes.insert(hash.to_string());
+                    check_max_cells(accepted_geohashes.len(), max_cells)?;
+                }
+            }
+        }
+    }
+    Ok(accepted_geohashes)
+}
+
+// ── Geometry → geohash ────────────────────────────────────────────────────────
+
+/// Geohash cells at `precision` covering any `geo` geometry.
+///
+/// Areal geometries follow `mode`; lines map to the cells they pass through and
+/// points to the cell containing them. Neither has an area to apply a mode to,
+/// so any mode other than `Intersects` is an `Error::InvalidArgument` for a
+/// point or line given on its own. Multi-geometries and collections are the
+/// union of their members, with `mode` applied to the areal ones.
+pub fn geometry_to_geohashes<M>(
+    geometry: &Geometry,
+    precision: usize,
+    mode: M,
+) -> Result<HashSet<String>, Error>
+where
+    M: Into<CoverageMode>,
+{
+    geometry_to_geohashes_bounded(geometry, precision, mode, None, &mut || false)
+}
+
+/// `geometry_to_geohashes` with a cell budget and a cancellation hook, as in
+/// `polygons_to_geohashes_bounded`.
+pub fn geometry_to_geohashes_bounded<M>(
+    geometry: &Geometry,
+    precision: usize,
+    mode: M,
+    max_cells: Option<usize>,
+    interrupted: &mut dyn FnMut() -> bool,
+) -> Result<HashSet<String>, Error>
+where
+    M: Into<CoverageMode>,
+{
+    let mode = mode.into();
+    let areal = !matches!(
+        geometry,
+        Geometry::Point(_)
+            | Geometry::MultiPoint(_)
+            | Geometry::Line(_)
+            | Geometry::LineString(_)
+            | Geometry::MultiLineString(_)
+    );
+    if !areal && mode != CoverageMode::Intersects {
+        return Err(Error::InvalidArgument(format!(
+            "{mode:?} coverage needs an areal geometry: lines and points only cover the \
+             cells they touch"
+        )));
+    }
+    let accepted_geohashes = geometry_cells(geometry, precision, mode, max_cells, interrupted)?;
+    check_max_cells(accepted_geohashes.len(), max_cells)?;
+    Ok(accepted_geohashes)
+}
+
+/// The cells of `geometry_to_geohashes_bounded`, with `mode` applied to areal
+/// geometries only: points and lines, including those inside a collection,
+/// always cover the cells they touch.
+fn geometry_cells(
+    geometry: &Geometry,
+    precision: usize,
+    mode: CoverageMode,
+    max_cells: Option<usize>,
+    interrupted: &mut dyn FnMut() -> bool,
+) -> Result<HashSet<String>, Error> {
+    let polygons = |polygons: Vec<Polygon>, interrupted: &mut dyn FnMut() -> bool| {
+        polygons_to_geohashes_bounded(polygons, precision, mode, max_cells, interrupted)
+    };
+    let lines = |lines: Vec<LineString>, interrupted: &mut dyn FnMut() -> bool| {
+        linestrings_to_geohashes_bounded(lines, precision, 0.0, max_cells, interrupted)
+    };
+    Ok(match geometry {
+        Geometry::Point(point) => HashSet::from([encode(point.0, precision)?]),
+        Geometry::MultiPoint(points) => points
+            .iter()
+            .map(|point| Ok(encode(point.0, precision)?))
+            .collect::<Result<_, Error>>()?,
+        Geometry::Line(line) => lines(vec![LineString::from(*line)], interrupted)?,
+        Geometry::LineString(line) => lines(vec![line.clone()], interrupted)?,
+        Geometry::MultiLineString(multi) => lines(multi.0.clone(), interrupted)?,
+        Geometry::Polygon(polygon) => polygons(vec![polygon.clone()], interrupted)?,
+        Geometry::MultiPolygon(multi) => polygons(multi.0.clone(), interrupted)?,
+        Geometry::Rect(rect) => polygons(vec![rect.to_polygon()], interrupted)?,
+        Geometry::Triangle(triangle) => polygons(vec![triangle.to_polygon()], interrupted)?,
+        Geometry::GeometryCollection(collection) => {
+            let mut accepted_geohashes = HashSet::new();
+            for member in collection {
+                accepted_geohashes.extend(geometry_cells(
+                    member,
+                    precision,
+                    mode,
+                    max_cells,
+                    interrupted,
+                )?);
+                check_max_cells(accepted_geohashes.len(), max_cells)?;
+            }
+            accepted_geohashes
+        }
+    })
+}
+
+// ── Precision selection ───────────────────────────────────────────────────────
+
+/// Estimated number of cells of `grid` covering `polygons`.
+///
+/// Works in grid units, where every cell is the same size: the interior is the
+/// area divided by the cell area, and the boundary adds the cells its edges
+/// cross. This errs on the high side, as when counting every intersecting cell.
+fn estimated_cell_count(polygons: &[Polygon], grid: &CellGrid) -> f64 {
+    let mut count = 0.0;
+    for piece in polygons.iter().flat_map(split_antimeridian) {
+        count += piece.unsigned_area() / (grid.cell_w * grid.cell_h);
+        for ring in std::iter::once(piece.exterior()).chain(piece.interiors()) {
+            for edge in ring.lines() {
+                count += (edge.dx().abs() / grid.cell_w) + (edge.dy().abs() / grid.cell_h);
             }
+        }
+    }
+    count.max(1.0)
+}
+
+/// Finest precision at which `polygons` fit the given constraints.
+///
+/// With `max_cells`, the estimated number of covering cells must not exceed it;
+/// with `target_cell_m`, cells must be at least that size, measured as the square
+/// root of their area at the polygons' middle latitude. Without either, this is
+/// 12. Fails with `Error::TooManyCells` if even precision 1 needs more than
+/// `max_cells` cells.
+pub fn choose_precision(
+    polygons: &[Polygon],
+    max_cells: Option<usize>,
+    target_cell_m: Option<f64>,
+) -> Result<usize, Error> {
+    let mid_lat = polygons
+        .iter()
+        .filter_map(|polygon| polygon.bounding_rect())
+        .map(|rect| rect.center().y)
+        .sum::<f64>()
+        / polygons.len().max(1) as f64;
+    let mut chosen = None;
+    for precision in 1..=12 {
+        let grid = CellGrid::new(precision)?;
+        let fits_count =
+            max_cells.is_none_or(|limit| estimated_cell_count(polygons, &grid) <= limit as f64);
+        let (cell_w_m, cell_h_m) = grid.cell_size_m(mid_lat);
+        let fits_size = target_cell_m.is_none_or(|target| (cell_w_m * cell_h_m).sqrt() >= target);
+        // precision 1 is always acceptable for a cell size target
+        if !fits_count || (!fits_size && precision > 1) {
+            break;
+        }
+        chosen = Some(precision);
+    }
+    chosen.ok_or(Error::TooManyCells {
+        limit: max_cells.unwrap_or(0),
+    })
+}
+
+/// Cover `polygons` at the precision picked by `choose_precision`.
+///
+/// `max_cells` also bounds the covering itself, failing with
+/// `Error::TooManyCells` should the estimate fall short.
+pub fn polygons_to_geohashes_auto<M>(
+    polygons: Vec<Polygon>,
+    mode: M,
+    max_cells: Option<usize>,
+    target_cell_m: Option<f64>,
+) -> Result<HashSet<String>, Error>
+where
+    M: Into<CoverageMode>,
+{
+    let precision = choose_precision(&polygons, max_cells, target_cell_m)?;
+    polygons_to_geohashes_bounded(polygons, precision, mode, max_cells, &mut || false)
+}
+
+// ── Polygon → mixed-precision geohash ─────────────────────────────────────────
 
-            let gh_bbox = decode_bbox(&current_geohash)?;
-            let current_geohash_polygon = gh_bbox.to_polygon();
+const BASE32: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// The 32 cells one precision level finer than `hash`.
+fn child_hashes(hash: &str) -> impl Iterator<Item = String> + '_ {
+    BASE32.iter().map(move |&c| {
+        let mut child = String::with_capacity(hash.len() + 1);
+        child.push_str(hash);
+        child.push(c as char);
+        child
+    })
+}
+
+/// Drop every hash whose ancestor (a proper prefix) is also in the set.
+fn drop_covered_descendants(geohashes: HashSet<String>) -> HashSet<String> {
+    geohashes
+        .iter()
+        .filter(|h| !(1..h.len()).any(|n| geohashes.contains(&h[..n])))
+        .cloned()
+        .collect()
+}
 
-            // prune non-intersecting cells early and don't expand from them
-            if !polygon.intersects(&current_geohash_polygon) {
-                rejected_geohashes.insert(current_geohash.clone());
+/// Cover `polygons` with a mixed-precision geohash set.
+///
+/// Cells fully inside a polygon are emitted at the coarsest precision in
+/// `min_precision..=max_precision`; only cells straddling the boundary are
+/// refined, down to `max_precision`. Expanding every returned cell to
+/// `max_precision` gives the same set as
+/// `polygons_to_geohashes(polygons, max_precision, fully_contained_only)`.
+pub fn polygons_to_geohashes_compact<PI>(
+    polygons: PI,
+    min_precision: usize,
+    max_precision: usize,
+    fully_contained_only: bool,
+) -> Result<HashSet<String>, Error>
+where
+    PI: IntoIterator<Item = Polygon>,
+{
+    polygons_to_geohashes_compact_bounded(
+        polygons,
+        min_precision,
+        max_precision,
+        fully_contained_only,
+        None,
+        &mut || false,
+    )
+}
+
+/// `polygons_to_geohashes_compact` with a cell budget and a cancellation hook,
+/// as in `polygons_to_geohashes_bounded`. The budget counts the mixed-precision
+/// cells accepted so far.
+pub fn polygons_to_geohashes_compact_bounded<PI>(
+    polygons: PI,
+    min_precision: usize,
+    max_precision: usize,
+    fully_contained_only: bool,
+    max_cells: Option<usize>,
+    interrupted: &mut dyn FnMut() -> bool,
+) -> Result<HashSet<String>, Error>
+where
+    PI: IntoIterator<Item = Polygon>,
+{
+    if max_precision > 12 {
+        return Err(Error::InvalidPrecision(max_precision));
+    }
+    if min_precision == 0 || min_precision > max_precision {
+        return Err(Error::InvalidPrecision(min_precision));
+    }
+    let mut accepted_geohashes = HashSet::new();
+    let mut walked = 0usize;
+
+    for part in polygons.into_iter().flat_map(grid_polygons) {
+        // Every cell intersecting the polygon at the coarsest level is a root
+        // of the refinement; children are only pushed if they intersect too.
+        let mut roots = HashSet::new();
+        for piece in part.walk_targets() {
+            walk_polygon_cells(piece, min_precision, |current_geohash, _| {
+                poll_interrupted(&mut walked, interrupted)?;
+                roots.insert(current_geohash.to_string());
+                Ok(())
+            })?;
+        }
+        let polygon = &part.whole;
+        let mut pending: Vec<String> = roots.into_iter().collect();
+
+        while let Some(current_geohash) = pending.pop() {
+            poll_interrupted(&mut walked, interrupted)?;
+            let current_geohash_polygon = decode_bbox(&current_geohash)?.to_polygon();
+
+            if cell_within_polygon(polygon, &current_geohash_polygon) {
+                accepted_geohashes.insert(current_geohash);
+            } else if current_geohash.len() < max_precision {
+                for child in child_hashes(&current_geohash) {
+                    if polygon.intersects(&decode_bbox(&child)?.to_polygon()) {
+                        pending.push(child);
+                    }
+                }
                 continue;
+            } else if !fully_contained_only {
+                accepted_geohashes.insert(current_geohash);
             }
+            check_max_cells(accepted_geohashes.len(), max_cells)?;
+        }
+    }
+    // Polygons of a multipolygon can share boundary cells, so one polygon may
+    // accept a coarse cell while another accepted some of its descendants.
+    Ok(drop_covered_descendants(accepted_geohashes))
+}
 
-            let accept = if fully_contained_only {
-                if has_holes {
-                    // robust path when holes exist
-                    polygon.contains(&current_geohash_polygon)
-                } else {
-                    // fast path for hole-free polygons (strict containment)
-                    !polygon_exterior.intersects(current_geohash_polygon.exterior())
-                        && current_geohash_polygon.unsigned_area() <= polygon.unsigned_area()
-                }
-            } else {
-                // intersecting is enough
-                true
-            };
+// ── Compaction ────────────────────────────────────────────────────────────────
 
-            if accept {
-                accepted_geohashes.insert(current_geohash.clone());
-            } else {
-                rejected_geohashes.insert(current_geohash.clone());
-            }
+/// Reject empty, over-long or non-base32 hashes.
+fn validate_geohash(hash: &str) -> Result<(), Error> {
+    if hash.is_empty() || hash.len() > 12 {
+        return Err(Error::InvalidHash(format!(
+            "{hash:?} must have between 1 and 12 characters"
+        )));
+    }
+    match hash
+        .chars()
+        .find(|c| !c.is_ascii() || !BASE32.contains(&(*c as u8)))
+    {
+        Some(c) => Err(Error::InvalidHash(format!(
+            "{hash:?} contains {c:?}, which is not in the geohash alphabet"
+        ))),
+        None => Ok(()),
+    }
+}
 
-            if let Ok(rez) = neighbors(&current_geohash) {
-                for neighbor in [rez.sw, rez.s, rez.se, rez.w, rez.e, rez.nw, rez.n, rez.ne] {
-                    if !accepted_geohashes.contains(&neighbor)
-                        && !rejected_geohashes.contains(&neighbor)
-                    {
-                        testing_geohashes.push_back(neighbor.to_string());
-                    }
+/// Merge complete groups of 32 sibling cells into their parent, level by level.
+///
+/// Cells already covered by an ancestor in the set are dropped first, so the
+/// result is the smallest mixed-precision set covering the same area.
+/// Returns an error if any hash is malformed.
+pub fn compact_geohash_set(geohashes: &HashSet<String>) -> Result<HashSet<String>, Error> {
+    geohashes.par_iter().try_for_each(|h| validate_geohash(h))?;
+    let mut compacted = drop_covered_descendants(geohashes.clone());
+    let finest = compacted.iter().map(String::len).max().unwrap_or(0);
+
+    // Walk from the finest level up so that merged parents can complete their
+    // own sibling group on the next iteration.
+    for level in (2..=finest).rev() {
+        let complete_parents: Vec<String> = compacted
+            .par_iter()
+            .filter(|h| h.len() == level)
+            .fold(HashMap::new, |mut counts: HashMap<&str, usize>, h| {
+                *counts.entry(&h[..level - 1]).or_insert(0) += 1;
+                counts
+            })
+            .reduce(HashMap::new, |mut a, b| {
+                for (parent, n) in b {
+                    *a.entry(parent).or_insert(0) += n;
                 }
+                a
+            })
+            .into_iter()
+            .filter(|&(_, n)| n == BASE32.len())
+            .map(|(parent, _)| parent.to_string())
+            .collect();
+        for parent in complete_parents {
+            for child in child_hashes(&parent) {
+                compacted.remove(&child);
             }
+            compacted.insert(parent);
         }
     }
-    Ok(accepted_geohashes)
+    Ok(compacted)
+}
+
+/// Replace every cell with all of its descendants at `precision`.
+///
+/// The inverse of `compact_geohash_set`. Returns an error if any hash is
+/// malformed or already finer than `precision`, and `Error::TooManyCells`
+/// before building anything if the cells have more than `max_cells`
+/// descendants between them.
+pub fn uncompact_geohash_set(
+    geohashes: &HashSet<String>,
+    precision: usize,
+    max_cells: Option<usize>,
+) -> Result<HashSet<String>, Error> {
+    if precision == 0 || precision > 12 {
+        return Err(Error::InvalidPrecision(precision));
+    }
+    geohashes.par_iter().try_for_each(|h| {
+        validate_geohash(h)?;
+        if h.len() > precision {
+            return Err(Error::InvalidHash(format!(
+                "{h} is finer than the target precision {precision}"
+            )));
+        }
+        Ok(())
+    })?;
+    // 32 descendants per level, saturating where they can't be counted
+    let count = geohashes.iter().fold(0usize, |count, h| {
+        let descendants = 1usize
+            .checked_shl(5 * (precision - h.len()) as u32)
+            .unwrap_or(usize::MAX);
+        count.saturating_add(descendants)
+    });
+    check_max_cells(count, max_cells)?;
+    Ok(geohashes
+        .par_iter()
+        .flat_map_iter(|h| {
+            let mut level = vec![h.clone()];
+            for _ in h.len()..precision {
+                level = level.iter().flat_map(|p| child_hashes(p)).collect();
+            }
+            level
+        })
+        .collect())
 }
 
 pub fn polygons_to_geohashes_handbrake<PI>(
     polygons: PI,
     precision: usize,
     inner: bool,
-) -> Result<HashSet<String>, GeohashError>
+) -> Result<HashSet<String>, Error>
 where
     PI: IntoIterator<Item = Polygon>,
 {
@@ -223,202 +1785,196 @@ where
     Ok(inner_geohashes)
 }
 
-/// Walk a `__geo_interface__` coordinate ring (list of [x, y] pairs) into a LineString.
-fn extract_ring(ring: &Bound<'_, PyAny>) -> PyResult<geo_types::LineString<f64>> {
-    let mut coords = Vec::new();
-    for (i, item) in ring.try_iter()?.enumerate() {
-        let pair = item?;
-        let (x, y) = (|| -> PyResult<(f64, f64)> {
-            Ok((pair.get_item(0)?.extract()?, pair.get_item(1)?.extract()?))
-        })()
-        .map_err(|_| {
-            pyo3::exceptions::PyValueError::new_err(format!(
-                "invalid coordinate at index {i}: expected [longitude, latitude]"
-            ))
-        })?;
-        coords.push(geo_types::Coord { x, y });
-    }
-    Ok(geo_types::LineString::new(coords))
-}
-
-/// Build a `Polygon` from a `__geo_interface__` coordinates value (list of rings).
-fn extract_polygon(coordinates: &Bound<'_, PyAny>) -> PyResult<Polygon<f64>> {
-    let mut iter = coordinates.try_iter()?;
-    let exterior = extract_ring(
-        &iter
-            .next()
-            .ok_or_else(|| pyo3::exceptions::PyValueError::new_err("Polygon has no rings"))??,
-    )?;
-    let holes = iter
-        .map(|r| -> PyResult<_> { extract_ring(&r?) })
-        .collect::<PyResult<Vec<_>>>()?;
-    Ok(Polygon::new(exterior, holes))
-}
-
-/// Build a `Vec<Polygon>` from a `__geo_interface__` MultiPolygon coordinates value.
-fn extract_multipolygon(coordinates: &Bound<'_, PyAny>) -> PyResult<Vec<Polygon<f64>>> {
-    coordinates
-        .try_iter()?
-        .map(|item| -> PyResult<_> { extract_polygon(&item?) })
-        .collect()
-}
+// ── Set algebra ───────────────────────────────────────────────────────────────
 
-#[pyfunction]
-fn polygon_to_geohashes(
-    _py: Python,
-    py_polygon: Bound<'_, PyAny>,
-    precision: usize,
-    inner: bool,
-) -> PyResult<HashSet<String>> {
-    let geo_interface = py_polygon.getattr("__geo_interface__").map_err(|_| {
-        pyo3::exceptions::PyValueError::new_err(
-            "Object does not implement __geo_interface__. Expected a Shapely Polygon or MultiPolygon.",
-        )
-    })?;
+// The operations below treat a set as the area its cells cover, so a cell
+// overlaps its own descendants and ancestors. Results never hold a cell
+// together with one of its ancestors; `compact_geohash_set` merges siblings.
 
-    let geom_type: String = geo_interface
-        .get_item("type")
-        .map_err(|_| {
-            pyo3::exceptions::PyValueError::new_err(
-                "__geo_interface__ mapping is missing the required 'type' key",
-            )
-        })?
-        .extract()
-        .map_err(|_| {
-            pyo3::exceptions::PyValueError::new_err(
-                "__geo_interface__ 'type' value must be a string",
-            )
-        })?;
+/// Validate both operands of a set operation.
+fn validate_operands(a: &HashSet<String>, b: &HashSet<String>) -> Result<(), Error> {
+    a.par_iter()
+        .chain(b.par_iter())
+        .try_for_each(|h| validate_geohash(h))
+}
 
-    let coordinates = geo_interface.get_item("coordinates").map_err(|_| {
-        pyo3::exceptions::PyValueError::new_err(
-            "__geo_interface__ mapping is missing the required 'coordinates' key",
-        )
-    })?;
+/// The area covered by either set.
+///
+/// Returns an error if any hash is malformed.
+pub fn geohash_union(a: &HashSet<String>, b: &HashSet<String>) -> Result<HashSet<String>, Error> {
+    validate_operands(a, b)?;
+    Ok(drop_covered_descendants(a.union(b).cloned().collect()))
+}
 
-    let polygons = match geom_type.as_str() {
-        "Polygon" => vec![extract_polygon(&coordinates)?],
-        "MultiPolygon" => extract_multipolygon(&coordinates)?,
-        _ => {
-            return Err(pyo3::exceptions::PyValueError::new_err(
-                "The geometry is not a Polygon or MultiPolygon",
-            ))
-        }
+/// The area covered by both sets: every cell of one set that lies inside a
+/// cell of the other.
+///
+/// Returns an error if any hash is malformed.
+pub fn geohash_intersection(
+    a: &HashSet<String>,
+    b: &HashSet<String>,
+) -> Result<HashSet<String>, Error> {
+    validate_operands(a, b)?;
+    let inside = |cells: &HashSet<String>, other: &HashSet<String>| {
+        cells
+            .par_iter()
+            .filter(|h| is_covered(other, h))
+            .cloned()
+            .collect::<Vec<_>>()
     };
+    let mut both: HashSet<String> = inside(a, b).into_iter().collect();
+    both.extend(inside(b, a));
+    Ok(drop_covered_descendants(both))
+}
+
+/// The area covered by `a` but not by `b`.
+///
+/// A cell of `a` that only partly overlaps `b` is split into its children,
+/// down to the precision of the cells of `b` inside it. Returns an error if
+/// any hash is malformed.
+pub fn geohash_difference(
+    a: &HashSet<String>,
+    b: &HashSet<String>,
+) -> Result<HashSet<String>, Error> {
+    validate_operands(a, b)?;
+    // proper prefixes of the cells of `b`: cells only partly covered by `b`
+    let partial: HashSet<&str> = b
+        .iter()
+        .flat_map(|h| (1..h.len()).map(move |n| &h[..n]))
+        .collect();
+    let kept = a
+        .par_iter()
+        .flat_map_iter(|h| {
+            let mut kept = Vec::new();
+            let mut pending = vec![h.clone()];
+            while let Some(cell) = pending.pop() {
+                if is_covered(b, &cell) {
+                    continue;
+                }
+                if partial.contains(cell.as_str()) {
+                    pending.extend(child_hashes(&cell));
+                } else {
+                    kept.push(cell);
+                }
+            }
+            kept
+        })
+        .collect();
+    Ok(drop_covered_descendants(kept))
+}
 
-    polygons_to_geohashes(polygons, precision, inner)
-        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("{e:?}")))
+/// Whether the area covered by `a` includes all of `b`.
+///
+/// Returns an error if any hash is malformed.
+pub fn geohash_contains(a: &HashSet<String>, b: &HashSet<String>) -> Result<bool, Error> {
+    Ok(geohash_difference(b, a)?.is_empty())
 }
 
-// ── Encode / decode ───────────────────────────────────────────────────────────
+// ── Cell hierarchy ────────────────────────────────────────────────────────────
 
-/// Encode a single (lng, lat) coordinate to a geohash of the given precision.
-#[pyfunction]
-#[pyo3(name = "encode")]
-fn encode_py(lng: f64, lat: f64, precision: usize) -> PyResult<String> {
-    encode((lng, lat).into(), precision)
-        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+/// The ancestor of `hash` at `precision`, which must not be finer than `hash`.
+pub fn geohash_parent(hash: &str, precision: usize) -> Result<String, Error> {
+    validate_geohash(hash)?;
+    if precision == 0 || precision > hash.len() {
+        return Err(Error::InvalidPrecision(precision));
+    }
+    Ok(hash[..precision].to_string())
 }
 
-/// Encode parallel lists of longitudes and latitudes to geohashes (parallel).
-#[pyfunction]
-#[pyo3(signature = (lngs, lats, precision, num_threads=None))]
-fn encode_many(
-    py: Python<'_>,
-    lngs: Vec<f64>,
-    lats: Vec<f64>,
+/// The descendants of `hash` at `precision`, in geohash order: 32 per level.
+///
+/// There are `32^(precision - hash.len())` of them, so they are generated one
+/// at a time rather than collected.
+pub fn geohash_children(
+    hash: &str,
     precision: usize,
-    num_threads: Option<usize>,
-) -> PyResult<Vec<String>> {
-    if lngs.len() != lats.len() {
-        return Err(pyo3::exceptions::PyValueError::new_err(
-            "lngs and lats must have the same length",
-        ));
+) -> Result<impl Iterator<Item = String> + '_, Error> {
+    validate_geohash(hash)?;
+    if precision < hash.len() || precision > 12 {
+        return Err(Error::InvalidPrecision(precision));
     }
-    let pool = make_pool(num_threads)?;
-    let raw: Vec<Result<String, GeohashError>> = py.allow_threads(|| {
-        run_with_pool(&pool, || {
-            lngs.into_par_iter()
-                .zip_eq(lats)
-                .map(|(lng, lat)| encode((lng, lat).into(), precision))
-                .collect()
-        })
-    });
-    raw.into_iter()
-        .map(|r| r.map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string())))
-        .collect()
+    let depth = (precision - hash.len()) as u64;
+    Ok((0..1u64 << (5 * depth)).map(move |index| {
+        let mut child = String::with_capacity(precision);
+        child.push_str(hash);
+        // the digits of `index` in base 32, most significant first
+        child.extend(
+            (0..depth)
+                .rev()
+                .map(|level| BASE32[(index >> (5 * level)) as usize & 31] as char),
+        );
+        child
+    }))
 }
 
-/// Decode a geohash to (lng, lat, lng_err, lat_err) — lng-first, matching encode convention.
-#[pyfunction]
-fn decode_exactly(hash_str: &str) -> PyResult<(f64, f64, f64, f64)> {
-    let bbox = decode_bbox(hash_str)
-        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
-    let lat = (bbox.min().y + bbox.max().y) / 2.0;
-    let lng = (bbox.min().x + bbox.max().x) / 2.0;
-    let lat_err = (bbox.max().y - bbox.min().y) / 2.0;
-    let lng_err = (bbox.max().x - bbox.min().x) / 2.0;
-    Ok((lng, lat, lng_err, lat_err))
-}
+// ── k-rings ───────────────────────────────────────────────────────────────────
 
-/// Decode a list of geohashes to (lng, lat) center pairs (parallel).
-#[pyfunction]
-#[pyo3(signature = (geohashes, num_threads=None))]
-fn decode_many(
-    py: Python<'_>,
-    geohashes: Vec<String>,
-    num_threads: Option<usize>,
-) -> PyResult<Vec<(f64, f64)>> {
-    let pool = make_pool(num_threads)?;
-    let raw: Vec<Result<(f64, f64), GeohashError>> = py.allow_threads(|| {
-        run_with_pool(&pool, || {
-            geohashes
-                .into_par_iter()
-                .map(|hash| {
-                    decode_bbox(&hash).map(|bbox| {
-                        let lat = (bbox.min().y + bbox.max().y) / 2.0;
-                        let lng = (bbox.min().x + bbox.max().x) / 2.0;
-                        (lng, lat)
-                    })
-                })
-                .collect()
+/// Cells whose step distance from `hash` is within `min_k..=max_k`.
+///
+/// A step is a move to one of the 8 neighbours, so distance is the larger of
+/// the row and column offsets, with columns wrapping across the antimeridian.
+/// As with `all_neighbors`, nothing lies beyond a pole. Cells come row by row
+/// from the south-west. The cells are counted before any is built, failing
+/// with `Error::TooManyCells` if there are more than `max_cells`.
+fn cells_at_steps(
+    hash: &str,
+    min_k: usize,
+    max_k: usize,
+    max_cells: Option<usize>,
+) -> Result<Vec<String>, Error> {
+    let grid = CellGrid::of_hash(hash)?;
+    let (col, row) = grid.index_of(hash)?;
+    let (min_k, max_k) = (min_k as i64, max_k.min(grid.cols as usize) as i64);
+    // a whole row once the span wraps onto itself
+    let (first_col, last_col) = if 2 * max_k + 1 >= grid.cols {
+        (col - grid.cols / 2, col - grid.cols / 2 + grid.cols - 1)
+    } else {
+        (col - max_k, col + max_k)
+    };
+    let rows = (row - max_k).max(0)..=(row + max_k).min(grid.rows - 1);
+    // rows closer than `min_k` lose the columns closer than `min_k` too
+    let width = last_col - first_col + 1;
+    let count: i64 = rows
+        .clone()
+        .map(|r| match (r - row).abs() < min_k {
+            true => width - (2 * min_k - 1).min(width),
+            false => width,
         })
-    });
-    raw.into_iter()
-        .map(|r| r.map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string())))
-        .collect()
+        .sum();
+    check_max_cells(count as usize, max_cells)?;
+    let mut cells = Vec::with_capacity(count as usize);
+    for r in rows {
+        for c in first_col..=last_col {
+            let col_steps = (c - col).abs().min(grid.cols - (c - col).abs());
+            let steps = (r - row).abs().max(col_steps);
+            if (min_k..=max_k).contains(&steps) {
+                cells.push(grid.hash_at(c, r)?);
+            }
+        }
+    }
+    Ok(cells)
 }
 
-/// Decode a list of geohashes to (lng, lat, lng_err, lat_err) tuples (parallel).
-#[pyfunction]
-#[pyo3(signature = (geohashes, num_threads=None))]
-fn decode_many_exactly(
-    py: Python<'_>,
-    geohashes: Vec<String>,
-    num_threads: Option<usize>,
-) -> PyResult<Vec<(f64, f64, f64, f64)>> {
-    let pool = make_pool(num_threads)?;
-    let raw: Vec<Result<(f64, f64, f64, f64), GeohashError>> = py.allow_threads(|| {
-        run_with_pool(&pool, || {
-            geohashes
-                .into_par_iter()
-                .map(|hash| {
-                    decode_bbox(&hash).map(|bbox| {
-                        let lat = (bbox.min().y + bbox.max().y) / 2.0;
-                        let lng = (bbox.min().x + bbox.max().x) / 2.0;
-                        let lat_err = (bbox.max().y - bbox.min().y) / 2.0;
-                        let lng_err = (bbox.max().x - bbox.min().x) / 2.0;
-                        (lng, lat, lng_err, lat_err)
-                    })
-                })
-                .collect()
-        })
-    });
-    raw.into_iter()
-        .map(|r| r.map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string())))
-        .collect()
+/// Every cell within `k` steps of `hash`: a square of up to `(2k + 1)²` cells
+/// centred on it, `hash` included. Fails with `Error::TooManyCells` before
+/// building more than `max_cells` cells.
+pub fn geohash_k_ring(
+    hash: &str,
+    k: usize,
+    max_cells: Option<usize>,
+) -> Result<Vec<String>, Error> {
+    cells_at_steps(hash, 0, k, max_cells)
+}
+
+/// The hollow square of cells exactly `k` steps from `hash`; `hash` itself for
+/// `k = 0`. Fails with `Error::TooManyCells` before building more than
+/// `max_cells` cells.
+pub fn geohash_ring(hash: &str, k: usize, max_cells: Option<usize>) -> Result<Vec<String>, Error> {
+    cells_at_steps(hash, k, k, max_cells)
 }
 
+// ── WKB / EWKB output ────────────────────────────────────────────────────────
+
 /// Serialize a bounding box as a little-endian WKB or EWKB polygon (1 ring, 5 points, closed).
 ///
 /// Pass `srid: None` for plain WKB (93 bytes). Pass `srid: Some(s)` for EWKB (97 bytes),
@@ -452,215 +2008,729 @@ fn geohashes_to_bytes(
     geohashes: Vec<String>,
     srid: Option<u32>,
     pool: &Option<rayon::ThreadPool>,
-) -> Vec<Result<Vec<u8>, GeohashError>> {
+) -> Vec<Result<Vec<u8>, Error>> {
     run_with_pool(pool, || {
         geohashes
             .into_par_iter()
             .map(|hash| {
-                decode_bbox(&hash).map(|bbox| {
-                    serialize_bbox(bbox.min().x, bbox.min().y, bbox.max().x, bbox.max().y, srid)
-                })
+                let (min, max) = decode_bbox(&hash).map(|bbox| (bbox.min(), bbox.max()))?;
+                Ok(serialize_bbox(min.x, min.y, max.x, max.y, srid))
             })
             .collect()
     })
 }
 
-fn into_py_wkb_results(raw: Vec<Result<Vec<u8>, GeohashError>>) -> PyResult<Vec<Vec<u8>>> {
-    raw.into_iter()
-        .map(|r| r.map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string())))
-        .collect()
-}
-
 /// Parallel Rust core of `decode_many_to_wkb`, without PyO3 overhead.
 pub fn geohashes_to_wkb(
     geohashes: Vec<String>,
     pool: &Option<rayon::ThreadPool>,
-) -> Vec<Result<Vec<u8>, GeohashError>> {
+) -> Vec<Result<Vec<u8>, Error>> {
     geohashes_to_bytes(geohashes, None, pool)
 }
 
-/// Decode a list of geohashes to WKB polygon bytes representing their bounding boxes (parallel).
-///
-/// Each returned bytes value is a standard little-endian WKB polygon with one ring of five
-/// points (closed bounding box). Pass the result to `ST_GeomFromWKB` in DuckDB or PostGIS.
-#[pyfunction]
-#[pyo3(signature = (geohashes, num_threads=None))]
-fn decode_many_to_wkb(
-    py: Python<'_>,
-    geohashes: Vec<String>,
-    num_threads: Option<usize>,
-) -> PyResult<Vec<Vec<u8>>> {
-    let pool = make_pool(num_threads)?;
-    into_py_wkb_results(py.allow_threads(|| geohashes_to_wkb(geohashes, &pool)))
-}
-
 /// Parallel Rust core of `decode_many_to_ewkb`, without PyO3 overhead.
 pub fn geohashes_to_ewkb(
     geohashes: Vec<String>,
     srid: u32,
     pool: &Option<rayon::ThreadPool>,
-) -> Vec<Result<Vec<u8>, GeohashError>> {
+) -> Vec<Result<Vec<u8>, Error>> {
     geohashes_to_bytes(geohashes, Some(srid), pool)
 }
 
-/// Decode a list of geohashes to EWKB polygon bytes with an embedded SRID (parallel).
-///
-/// Like `decode_many_to_wkb` but with a SRID embedded in the header, making the
-/// bytes suitable for direct insertion into PostGIS geometry columns without a
-/// separate `ST_SetSRID` call. `srid` defaults to 4326.
-#[pyfunction]
-#[pyo3(signature = (geohashes, srid=4326, num_threads=None))]
-fn decode_many_to_ewkb(
-    py: Python<'_>,
-    geohashes: Vec<String>,
-    srid: u32,
-    num_threads: Option<usize>,
-) -> PyResult<Vec<Vec<u8>>> {
-    let pool = make_pool(num_threads)?;
-    into_py_wkb_results(py.allow_threads(|| geohashes_to_ewkb(geohashes, srid, &pool)))
-}
+// ── Geohash set → dissolved polygon ──────────────────────────────────────────
+
+/// Dissolve a (possibly mixed-precision) geohash set into one MultiPolygon.
+///
+/// The set is compacted first so every region is built from as few boxes as
+/// possible; the boxes are decoded in parallel and unioned, so adjacent cells
+/// merge and enclosed gaps come out as holes.
+/// Returns an error if any hash is malformed.
+pub fn geohashes_to_multipolygon(geohashes: &HashSet<String>) -> Result<MultiPolygon, Error> {
+    let cells: Vec<Polygon> = compact_geohash_set(geohashes)?
+        .into_par_iter()
+        .map(|hash| decode_bbox(&hash).map(|bbox| bbox.to_polygon()))
+        .collect::<Result<_, _>>()?;
+    Ok(unary_union(&cells))
+}
+
+/// Append a WKB linear ring: point count followed by little-endian coordinates.
+fn write_wkb_ring(buf: &mut Vec<u8>, ring: &LineString) {
+    buf.extend_from_slice(&(ring.0.len() as u32).to_le_bytes());
+    for coord in &ring.0 {
+        buf.extend_from_slice(&coord.x.to_le_bytes());
+        buf.extend_from_slice(&coord.y.to_le_bytes());
+    }
+}
+
+/// Serialize a MultiPolygon as little-endian WKB, or EWKB when `srid` is set.
+///
+/// Only the outer MultiPolygon header carries the SRID; member polygons are
+/// plain WKB polygons, as PostGIS expects.
+pub fn serialize_multipolygon(mp: &MultiPolygon, srid: Option<u32>) -> Vec<u8> {
+    let wkb_type = if srid.is_some() {
+        6u32 | 0x20000000u32
+    } else {
+        6u32
+    };
+    let mut buf = Vec::new();
+    buf.push(0x01u8);
+    buf.extend_from_slice(&wkb_type.to_le_bytes());
+    if let Some(s) = srid {
+        buf.extend_from_slice(&s.to_le_bytes());
+    }
+    buf.extend_from_slice(&(mp.0.len() as u32).to_le_bytes());
+    for polygon in &mp.0 {
+        buf.push(0x01u8);
+        buf.extend_from_slice(&3u32.to_le_bytes());
+        buf.extend_from_slice(&(1 + polygon.interiors().len() as u32).to_le_bytes());
+        write_wkb_ring(&mut buf, polygon.exterior());
+        for hole in polygon.interiors() {
+            write_wkb_ring(&mut buf, hole);
+        }
+    }
+    buf
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+#[allow(clippy::items_after_test_module)]
+mod tests {
+    use super::*;
+    use geohash::decode_bbox;
+
+    fn read_f64_le(buf: &[u8], offset: usize) -> f64 {
+        f64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap())
+    }
+
+    /// Parse a WKB or EWKB bbox polygon and return (xmin, ymin, xmax, ymax).
+    /// Pass `srid: None` for plain WKB, `srid: Some(s)` to also assert the embedded SRID.
+    fn parse_polygon_bbox(buf: &[u8], srid: Option<u32>) -> (f64, f64, f64, f64) {
+        let has_srid = srid.is_some();
+        let expected_len = if has_srid { 97 } else { 93 };
+        let expected_type = if has_srid { 3u32 | 0x20000000u32 } else { 3u32 };
+        // header: byte_order(1) + type(4) [+ srid(4)] + rings(4) + points(4)
+        let coord_offset = if has_srid { 17usize } else { 13usize };
+        assert_eq!(buf.len(), expected_len);
+        assert_eq!(buf[0], 0x01, "byte order must be little-endian");
+        assert_eq!(u32::from_le_bytes(buf[1..5].try_into().unwrap()), expected_type, "WKB type mismatch");
+        let mut offset = 5;
+        if let Some(expected_srid) = srid {
+            assert_eq!(u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap()), expected_srid, "SRID mismatch");
+            offset += 4;
+        }
+        assert_eq!(u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap()), 1, "ring count must be 1");
+        assert_eq!(u32::from_le_bytes(buf[offset + 4..offset + 8].try_into().unwrap()), 5, "point count must be 5");
+        // Points: (xmin,ymin), (xmax,ymin), (xmax,ymax), (xmin,ymax), (xmin,ymin)
+        (
+            read_f64_le(buf, coord_offset),      // xmin — point 0 x
+            read_f64_le(buf, coord_offset + 8),  // ymin — point 0 y
+            read_f64_le(buf, coord_offset + 16), // xmax — point 1 x
+            read_f64_le(buf, coord_offset + 40), // ymax — point 2 y
+        )
+    }
+
+    // ── polygon_groups_to_geohashes ──────────────────────────────────────────
+
+    #[test]
+    fn test_polygon_groups_match_single_calls_in_order() {
+        let small = Polygon::new(
+            geo_types::LineString::from(vec![
+                (-99.1795917, 19.432134),
+                (-99.1656847, 19.429034),
+                (-99.1776492, 19.414236),
+                (-99.1795917, 19.432134),
+            ]),
+            vec![],
+        );
+        let groups = vec![vec![montreal_pentagon()], vec![], vec![small.clone()]];
+        let pool = Some(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(2)
+                .build()
+                .unwrap(),
+        );
+        let results = polygon_groups_to_geohashes(groups, 5, false, &pool);
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results[0].as_ref().unwrap(),
+            &polygons_to_geohashes(vec![montreal_pentagon()], 5, false).unwrap()
+        );
+        assert!(results[1].as_ref().unwrap().is_empty());
+        assert_eq!(
+            results[2].as_ref().unwrap(),
+            &polygons_to_geohashes(vec![small], 5, false).unwrap()
+        );
+    }
+
+    // ── CoverageMode ─────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_bool_mode_matches_enum_mode() {
+        for (inner, mode) in [
+            (true, CoverageMode::Contained),
+            (false, CoverageMode::Intersects),
+        ] {
+            assert_eq!(
+                polygons_to_geohashes(vec![montreal_pentagon()], 5, inner).unwrap(),
+                polygons_to_geohashes(vec![montreal_pentagon()], 5, mode).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_modes_are_nested() {
+        let cover = |mode| polygons_to_geohashes(vec![montreal_pentagon()], 5, mode).unwrap();
+        let contained = cover(CoverageMode::Contained);
+        let half = cover(CoverageMode::MinOverlap(0.5));
+        let centroid = cover(CoverageMode::Centroid);
+        let intersects = cover(CoverageMode::Intersects);
+        assert!(contained.is_subset(&half));
+        assert!(contained.is_subset(&centroid));
+        assert!(half.is_subset(&intersects));
+        assert!(centroid.is_subset(&intersects));
+        assert!(contained.len() < half.len() && half.len() < intersects.len());
+        assert_eq!(cover(CoverageMode::MinOverlap(1.0)), contained);
+    }
+
+    #[test]
+    fn test_min_overlap_adds_up_multipolygon_parts() {
+        let band = |w: f64, e: f64| Rect::new((w, 45.0), (e, 45.2)).to_polygon();
+        let mode = CoverageMode::MinOverlap(0.9);
+        let whole = polygons_to_geohashes([band(10.0, 10.3)], 5, mode).unwrap();
+        // 10.16 cuts a column of cells about 20/80, so neither half reaches 0.9
+        let halves =
+            polygons_to_geohashes([band(10.0, 10.16), band(10.16, 10.3)], 5, mode).unwrap();
+        assert_eq!(halves, whole);
+        assert!(halves.contains(&encode((10.16, 45.1).into(), 5).unwrap()));
+    }
+
+    #[test]
+    fn test_min_overlap_fraction_is_validated() {
+        for fraction in [-0.1, 1.5, f64::NAN] {
+            let mode = CoverageMode::MinOverlap(fraction);
+            assert!(matches!(
+                polygons_to_geohashes(vec![montreal_pentagon()], 5, mode),
+                Err(Error::InvalidArgument(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_min_overlap_matches_weights() {
+        let weights = polygons_to_geohash_weights(vec![montreal_pentagon()], 5).unwrap();
+        let expected: HashSet<String> = weights
+            .into_iter()
+            .filter(|(_, w)| *w >= 0.3)
+            .map(|(h, _)| h)
+            .collect();
+        let mode = CoverageMode::MinOverlap(0.3);
+        assert_eq!(
+            polygons_to_geohashes(vec![montreal_pentagon()], 5, mode).unwrap(),
+            expected
+        );
+    }
+
+    // ── polygons_to_geohashes_bounded ────────────────────────────────────────
+
+    #[test]
+    fn test_max_cells_fails_fast() {
+        let full = polygons_to_geohashes(vec![montreal_pentagon()], 6, false).unwrap();
+        let mut polls = 0;
+        let result = polygons_to_geohashes_bounded(
+            vec![montreal_pentagon()],
+            6,
+            false,
+            Some(100),
+            &mut || {
+                polls += 1;
+                false
+            },
+        );
+        assert_eq!(result, Err(Error::TooManyCells { limit: 100 }));
+        // stopped right at the limit, well before walking the whole polygon
+        assert_eq!(polls, 0);
+
+        let bounded = polygons_to_geohashes_bounded(
+            vec![montreal_pentagon()],
+            6,
+            false,
+            Some(full.len()),
+            &mut || false,
+        );
+        assert_eq!(bounded, Ok(full));
+    }
+
+    #[test]
+    fn test_interrupted_walk_stops() {
+        let mut polls = 0;
+        let result =
+            polygons_to_geohashes_bounded(vec![montreal_pentagon()], 6, true, None, &mut || {
+                polls += 1;
+                true
+            });
+        assert_eq!(result, Err(Error::Interrupted));
+        assert_eq!(polls, 1);
+
+        let collection = Geometry::GeometryCollection(geo::GeometryCollection(vec![
+            Geometry::Point(Point::new(-73.5, 45.5)),
+            Geometry::Polygon(montreal_pentagon()),
+        ]));
+        assert_eq!(
+            geometry_to_geohashes_bounded(&collection, 6, false, None, &mut || true),
+            Err(Error::Interrupted)
+        );
+        assert!(matches!(
+            geometry_to_geohashes_bounded(&collection, 6, false, Some(10), &mut || false),
+            Err(Error::TooManyCells { limit: 10 })
+        ));
+    }
+
+    #[test]
+    fn test_budget_and_interrupt_cover_every_walk() {
+        let line = LineString::from(vec![(-73.6, 45.5), (-73.0, 45.9)]);
+        let center = Point::new(-73.554, 45.508);
+        type Walk<'a> =
+            &'a dyn Fn(Option<usize>, &mut dyn FnMut() -> bool) -> Result<HashSet<String>, Error>;
+        let walks: [Walk; 4] = [
+            &|max_cells, interrupted| {
+                linestrings_to_geohashes_bounded([line.clone()], 9, 0.0, max_cells, interrupted)
+            },
+            &|max_cells, interrupted| {
+                linestrings_to_geohashes_bounded([line.clone()], 8, 50.0, max_cells, interrupted)
+            },
+            &|max_cells, interrupted| {
+                let polygons = vec![montreal_pentagon()];
+                polygons_to_geohashes_compact_bounded(polygons, 3, 7, false, max_cells, interrupted)
+            },
+            &|max_cells, interrupted| {
+                circle_to_geohashes_bounded(center, 2000.0, 8, false, max_cells, interrupted)
+            },
+        ];
+        for walk in walks {
+            let full = walk(None, &mut || false).unwrap();
+            assert_eq!(walk(Some(full.len()), &mut || false), Ok(full.clone()));
+            assert_eq!(
+                walk(Some(100), &mut || false),
+                Err(Error::TooManyCells { limit: 100 })
+            );
+            assert_eq!(walk(None, &mut || true), Err(Error::Interrupted));
+        }
+    }
+
+    // ── polygons_to_geohash_weights ──────────────────────────────────────────
+
+    #[test]
+    fn test_weights_match_coverage_modes() {
+        let weights = polygons_to_geohash_weights(vec![montreal_pentagon()], 5).unwrap();
+        let outer = polygons_to_geohashes(vec![montreal_pentagon()], 5, false).unwrap();
+        let inner = polygons_to_geohashes(vec![montreal_pentagon()], 5, true).unwrap();
+        assert!(weights.keys().all(|h| outer.contains(h)));
+        for (h, w) in &weights {
+            assert!(*w > 0.0 && *w <= 1.0, "{h} has weight {w}");
+            assert_eq!(*w == 1.0, inner.contains(h), "{h} has weight {w}");
+        }
+    }
+
+    #[test]
+    fn test_weights_sum_to_polygon_area() {
+        let polygon = montreal_pentagon();
+        let weights = polygons_to_geohash_weights(vec![polygon.clone()], 5).unwrap();
+        let covered: f64 = weights
+            .iter()
+            .map(|(h, w)| w * decode_bbox(h).unwrap().to_polygon().unsigned_area())
+            .sum();
+        assert!((covered - polygon.unsigned_area()).abs() < 1e-9);
+    }
+
+    // ── circle_to_geohashes ──────────────────────────────────────────────────
+
+    /// Every cell at `precision` whose centre lies in the lng/lat window, by brute force.
+    fn cells_in_window(min: (f64, f64), max: (f64, f64), precision: usize) -> Vec<String> {
+        let step = decode_bbox(&encode((min.0, min.1).into(), precision).unwrap()).unwrap();
+        let (dx, dy) = (step.width(), step.height());
+        let mut out = Vec::new();
+        let mut y = min.1;
+        while y <= max.1 {
+            let mut x = min.0;
+            while x <= max.0 {
+                let lng = (x + 180.0).rem_euclid(360.0) - 180.0;
+                out.push(encode((lng, y).into(), precision).unwrap());
+                x += dx;
+            }
+            y += dy;
+        }
+        out
+    }
+
+    fn brute_force_circle(
+        center: Point,
+        radius_m: f64,
+        precision: usize,
+        inner: bool,
+    ) -> HashSet<String> {
+        let deg = radius_m / 111_000.0 * 1.5;
+        let lng_deg = deg / center.y().to_radians().cos();
+        cells_in_window(
+            (center.x() - lng_deg, center.y() - deg),
+            (center.x() + lng_deg, center.y() + deg),
+            precision,
+        )
+        .into_iter()
+        .filter(|h| {
+            let rect = decode_bbox(h).unwrap();
+            let corners = rect.to_polygon().exterior().points().collect::<Vec<_>>();
+            if inner {
+                corners
+                    .iter()
+                    .all(|c| Geodesic.distance(center, *c) <= radius_m)
+            } else {
+                Geodesic.distance(center, closest_point_in_cell(center, &rect)) <= radius_m
+            }
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_closest_point_in_cell_beats_boundary_samples() {
+        // the last cell sits just across the antimeridian from its centre
+        let across = encode((-179.99, 0.01).into(), 4).unwrap();
+        for (center, hash) in [
+            (Point::new(-73.554, 45.508), "f25dvz"),
+            (Point::new(-73.554, 45.508), "f25dxb"),
+            (Point::new(-72.0, 47.0), "f25d"),
+            (Point::new(179.99, 0.0), across.as_str()),
+        ] {
+            let rect = decode_bbox(hash).unwrap();
+            let best = Geodesic.distance(center, closest_point_in_cell(center, &rect));
+            let sampled = rect
+                .to_polygon()
+                .exterior()
+                .lines()
+                .flat_map(|l| (0..=200).map(move |i| l.start + l.delta() * (i as f64 / 200.0)))
+                .map(|c| Geodesic.distance(center, c.into()))
+                .fold(f64::MAX, f64::min);
+            assert!(best <= sampled + 1e-6, "{hash}: {best} > {sampled}");
+            assert!(
+                best >= sampled - 0.01 * sampled.max(1.0),
+                "{hash}: {best} << {sampled}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_circle_matches_brute_force() {
+        for (center, precision) in [
+            (Point::new(-73.554, 45.508), 7),
+            (Point::new(-135.05, 60.72), 7),
+            (Point::new(179.995, -16.5), 6),
+        ] {
+            for inner in [false, true] {
+                assert_eq!(
+                    circle_to_geohashes(center, 800.0, precision, inner).unwrap(),
+                    brute_force_circle(center, 800.0, precision, inner),
+                    "center={center:?} inner={inner}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_circle_stays_round_at_high_latitude() {
+        let cells = circle_to_geohashes(Point::new(-135.05, 60.72), 2000.0, 7, false).unwrap();
+        let (mut lng_span, mut lat_span) = ((f64::MAX, f64::MIN), (f64::MAX, f64::MIN));
+        for h in &cells {
+            let rect = decode_bbox(h).unwrap();
+            lng_span = (lng_span.0.min(rect.min().x), lng_span.1.max(rect.max().x));
+            lat_span = (lat_span.0.min(rect.min().y), lat_span.1.max(rect.max().y));
+        }
+        // ~2 km either side: about twice as many degrees of longitude as latitude at 60°N
+        let ratio = (lng_span.1 - lng_span.0) / (lat_span.1 - lat_span.0);
+        assert!((1.8..2.3).contains(&ratio), "lng/lat span ratio {ratio}");
+    }
+
+    #[test]
+    fn test_circle_degenerate_radius() {
+        let center = Point::new(-73.554, 45.508);
+        let seed = encode(center.into(), 7).unwrap();
+        assert_eq!(
+            circle_to_geohashes(center, 0.0, 7, false).unwrap(),
+            hash_set(&[&seed])
+        );
+        assert!(circle_to_geohashes(center, 0.0, 7, true)
+            .unwrap()
+            .is_empty());
+        for radius_m in [-1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                circle_to_geohashes(center, radius_m, 7, false),
+                Err(Error::InvalidArgument(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_circles_preserve_order() {
+        let circles = vec![
+            (Point::new(-73.554, 45.508), 300.0),
+            (Point::new(-87.6298, 41.8781), 600.0),
+        ];
+        let results = circles_to_geohashes(circles.clone(), 7, false, &None);
+        for ((center, radius_m), result) in circles.into_iter().zip(results) {
+            assert_eq!(
+                result.unwrap(),
+                circle_to_geohashes(center, radius_m, 7, false).unwrap()
+            );
+        }
+    }
+
+    // ── polygons_to_geohashes_compact ────────────────────────────────────────
+
+    fn montreal_pentagon() -> Polygon {
+        Polygon::new(
+            geo_types::LineString::from(vec![
+                (-74.0, 45.3),
+                (-73.3, 45.35),
+                (-73.2, 45.7),
+                (-73.6, 45.85),
+                (-74.05, 45.6),
+                (-74.0, 45.3),
+            ]),
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test_compact_explodes_to_fixed_precision_result() {
+        for inner in [false, true] {
+            let compact =
+                polygons_to_geohashes_compact(vec![montreal_pentagon()], 3, 6, inner).unwrap();
+            let fixed = polygons_to_geohashes(vec![montreal_pentagon()], 6, inner).unwrap();
+            assert_eq!(
+                uncompact_geohash_set(&compact, 6, None).unwrap(),
+                fixed,
+                "inner={inner}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_compact_is_mixed_precision_and_smaller() {
+        let compact =
+            polygons_to_geohashes_compact(vec![montreal_pentagon()], 3, 6, false).unwrap();
+        let fixed = polygons_to_geohashes(vec![montreal_pentagon()], 6, false).unwrap();
+        assert!(compact.iter().any(|h| h.len() < 6));
+        assert!(compact.iter().all(|h| (3..=6).contains(&h.len())));
+        assert!(compact.len() < fixed.len());
+    }
+
+    #[test]
+    fn test_compact_invalid_precision_range() {
+        assert!(polygons_to_geohashes_compact(vec![montreal_pentagon()], 6, 5, false).is_err());
+        assert!(polygons_to_geohashes_compact(vec![montreal_pentagon()], 0, 5, false).is_err());
+        assert!(polygons_to_geohashes_compact(vec![montreal_pentagon()], 5, 13, false).is_err());
+    }
+
+    // ── choose_precision ─────────────────────────────────────────────────────
+
+    #[test]
+    fn test_choose_precision_fits_max_cells() {
+        let polygons = [montreal_pentagon()];
+        for limit in [20, 300, 5_000, 100_000] {
+            let precision = choose_precision(&polygons, Some(limit), None).unwrap();
+            let cells = polygons_to_geohashes(polygons.to_vec(), precision, false).unwrap();
+            let finer = polygons_to_geohashes(polygons.to_vec(), precision + 1, false).unwrap();
+            assert!(cells.len() <= limit, "{limit}: {} cells", cells.len());
+            // the estimate is close enough to not give up a whole level
+            assert!(
+                finer.len() > limit / 4,
+                "{limit}: {} finer cells",
+                finer.len()
+            );
+        }
+        assert_eq!(
+            choose_precision(&polygons, Some(0), None),
+            Err(Error::TooManyCells { limit: 0 })
+        );
+        assert_eq!(choose_precision(&polygons, None, None), Ok(12));
+    }
+
+    #[test]
+    fn test_choose_precision_fits_target_cell_size() {
+        let polygons = [montreal_pentagon()];
+        for target_m in [50.0, 1_000.0, 20_000.0] {
+            let precision = choose_precision(&polygons, None, Some(target_m)).unwrap();
+            let size_m = |p| {
+                let (w, h) = CellGrid::new(p).unwrap().cell_size_m(45.575);
+                (w * h).sqrt()
+            };
+            assert!(size_m(precision) >= target_m);
+            assert!(size_m(precision + 1) < target_m);
+        }
+        assert_eq!(choose_precision(&polygons, None, Some(1e8)), Ok(1));
+        // both constraints: the coarser of the two wins
+        assert_eq!(
+            choose_precision(&polygons, Some(20), Some(50.0)),
+            choose_precision(&polygons, Some(20), None)
+        );
+    }
+
+    #[test]
+    fn test_auto_matches_chosen_precision() {
+        let precision = choose_precision(&[montreal_pentagon()], Some(1_000), None).unwrap();
+        assert_eq!(
+            polygons_to_geohashes_auto(vec![montreal_pentagon()], true, Some(1_000), None),
+            polygons_to_geohashes(vec![montreal_pentagon()], precision, true)
+        );
+    }
+
+    // ── compact_geohash_set / uncompact_geohash_set ──────────────────────────
+
+    fn hash_set(hashes: &[&str]) -> HashSet<String> {
+        hashes.iter().map(|h| h.to_string()).collect()
+    }
 
-// ── Geography expansion ───────────────────────────────────────────────────────
+    #[test]
+    fn test_compact_merges_full_sibling_groups_recursively() {
+        // All 1024 grandchildren of "f2" collapse to "f2"; the lone "dr5" stays.
+        let mut input = uncompact_geohash_set(&hash_set(&["f2"]), 4, None).unwrap();
+        input.insert("dr5".to_string());
+        assert_eq!(
+            compact_geohash_set(&input).unwrap(),
+            hash_set(&["f2", "dr5"])
+        );
+    }
 
-fn n_hops_for(sample_hash: &str, expansion_m: f64) -> PyResult<usize> {
-    if !expansion_m.is_finite() || expansion_m < 0.0 {
-        return Err(pyo3::exceptions::PyValueError::new_err(
-            "expansion_m must be a finite non-negative number",
-        ));
+    #[test]
+    fn test_compact_keeps_incomplete_groups() {
+        let mut input = uncompact_geohash_set(&hash_set(&["f2h"]), 4, None).unwrap();
+        input.remove("f2h0");
+        assert_eq!(compact_geohash_set(&input).unwrap(), input);
     }
-    let bbox = decode_bbox(sample_hash)
-        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("invalid geohash: {e}")))?;
-    let lat_center = (bbox.min().y + bbox.max().y) / 2.0;
-    let cell_height_m = (bbox.max().y - bbox.min().y) * 111_000.0;
-    let cell_width_m = (bbox.max().x - bbox.min().x) * 111_320.0 * lat_center.to_radians().cos();
-    let min_cell_m = cell_height_m.min(cell_width_m);
-    Ok((expansion_m / min_cell_m).ceil() as usize)
-}
 
-/// Expand a single group of geohashes outward by `expansion_m` metres.
-///
-/// The hop count is derived from the cell height of a sample hash, so it works
-/// for any precision level.
-#[pyfunction]
-fn expand_geohashes(py: Python<'_>, geohashes: Vec<String>, expansion_m: f64) -> PyResult<Vec<String>> {
-    if geohashes.is_empty() {
-        return Ok(vec![]);
+    #[test]
+    fn test_compact_drops_covered_descendants() {
+        let input = hash_set(&["f2h", "f2h3", "f2h3z"]);
+        assert_eq!(compact_geohash_set(&input).unwrap(), hash_set(&["f2h"]));
     }
-    let expected_len = geohashes.first().unwrap().len();
-    if geohashes.iter().any(|h| h.len() != expected_len) {
-        return Err(pyo3::exceptions::PyValueError::new_err(
-            "all geohashes must have the same precision",
-        ));
+
+    #[test]
+    fn test_compact_invalid_geohash() {
+        assert!(compact_geohash_set(&hash_set(&["f2h", "not-a-geohash!"])).is_err());
+        assert!(compact_geohash_set(&hash_set(&[""])).is_err());
     }
-    let n_hops = n_hops_for(geohashes.first().unwrap(), expansion_m)?;
-    let hash_set: HashSet<String> = geohashes.into_iter().collect();
-    py.allow_threads(|| expand_geohash_set(&hash_set, n_hops))
-        .map(|s| s.into_iter().collect())
-        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
-}
 
-/// Expand multiple groups of geohashes outward by `expansion_m` metres.
-///
-/// Each input group is expanded independently. Output order matches input order —
-/// `result[i]` is the expanded version of `groups[i]`. Groups are processed in
-/// parallel across geographies via Rayon.
-///
-/// The hop count is derived per group from the cell height of the group's first
-/// hash, so groups at different precision levels are each handled correctly.
-#[pyfunction]
-fn expand_geohash_mapping(
-    py: Python<'_>,
-    groups: Vec<Vec<String>>,
-    expansion_m: f64,
-) -> PyResult<Vec<Vec<String>>> {
-    if groups.is_empty() {
-        return Ok(vec![]);
+    #[test]
+    fn test_uncompact_mixed_precision() {
+        let out = uncompact_geohash_set(&hash_set(&["f2h", "dr5ru"]), 5, None).unwrap();
+        assert_eq!(out.len(), 32 * 32 + 1);
+        assert!(out.iter().all(|h| h.len() == 5));
+        assert!(out.contains("dr5ru"));
     }
-    // Compute n_hops per group sequentially (fast, may raise PyErr) before releasing the GIL.
-    let n_hops_per_group: Vec<usize> = groups
-        .iter()
-        .map(|g| match g.first() {
-            Some(h) => {
-                let expected_len = h.len();
-                if g.iter().any(|gh| gh.len() != expected_len) {
-                    return Err(pyo3::exceptions::PyValueError::new_err(
-                        "all geohashes in a group must have the same precision",
-                    ));
-                }
-                n_hops_for(h, expansion_m)
-            }
-            None => Ok(0),
-        })
-        .collect::<PyResult<_>>()?;
 
-    let raw: Vec<Result<Vec<String>, GeohashError>> = py.allow_threads(|| {
-        groups
-            .into_par_iter()
-            .zip(n_hops_per_group.into_par_iter())
-            .map(|(hashes, n_hops)| {
-                let hash_set: HashSet<String> = hashes.into_iter().collect();
-                expand_geohash_set(&hash_set, n_hops).map(|s| s.into_iter().collect())
+    #[test]
+    fn test_uncompact_finer_than_target_errors() {
+        assert!(uncompact_geohash_set(&hash_set(&["dr5ru7"]), 5, None).is_err());
+        assert!(uncompact_geohash_set(&hash_set(&["dr5"]), 13, None).is_err());
+    }
+
+    #[test]
+    fn test_uncompact_size_is_bounded() {
+        let set = hash_set(&["f2h", "dr5ru"]);
+        assert_eq!(
+            uncompact_geohash_set(&set, 5, Some(1025)).unwrap().len(),
+            1025
+        );
+        assert_eq!(
+            uncompact_geohash_set(&set, 5, Some(1024)),
+            Err(Error::TooManyCells { limit: 1024 })
+        );
+        // counted up front, far past what could be built
+        assert_eq!(
+            uncompact_geohash_set(&hash_set(&["f", "d"]), 12, Some(1_000_000)),
+            Err(Error::TooManyCells { limit: 1_000_000 })
+        );
+    }
+
+    #[test]
+    fn test_errors_are_typed() {
+        assert_eq!(
+            uncompact_geohash_set(&hash_set(&["dr5"]), 13, None),
+            Err(Error::InvalidPrecision(13))
+        );
+        assert!(matches!(
+            compact_geohash_set(&hash_set(&["dr5", "dra"])),
+            Err(Error::InvalidHash(_))
+        ));
+        assert!(matches!(
+            expand_geohash_set_by_distance(&hash_set(&["dr5ri"]), 10.0),
+            Err(Error::InvalidHash(_))
+        ));
+        // a hash too long is a bad hash, not a bad precision
+        assert!(matches!(
+            all_neighbors(&"s".repeat(13)),
+            Err(Error::InvalidHash(_))
+        ));
+        let out_of_range = LineString::from(vec![(0.0, 0.0), (0.0, 95.0)]);
+        assert_eq!(
+            linestrings_to_geohashes([out_of_range], 6, 0.0),
+            Err(Error::InvalidCoordinate {
+                lng: 0.0,
+                lat: 95.0
             })
-            .collect()
-    });
-    raw.into_iter()
-        .map(|r| r.map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string())))
-        .collect()
-}
+        );
+        assert_eq!(
+            circle_to_geohashes(Point::new(0.0, 0.0), 10.0, 0, false),
+            Err(Error::InvalidPrecision(0))
+        );
+    }
 
-// ── Module ────────────────────────────────────────────────────────────────────
-
-#[pymodule]
-fn geohash_polygon(m: &Bound<'_, PyModule>) -> PyResult<()> {
-    m.add_function(wrap_pyfunction!(polygon_to_geohashes, m)?)?;
-    m.add_function(wrap_pyfunction!(encode_py, m)?)?;
-    m.add_function(wrap_pyfunction!(encode_many, m)?)?;
-    m.add_function(wrap_pyfunction!(decode_exactly, m)?)?;
-    m.add_function(wrap_pyfunction!(decode_many, m)?)?;
-    m.add_function(wrap_pyfunction!(decode_many_exactly, m)?)?;
-    m.add_function(wrap_pyfunction!(decode_many_to_wkb, m)?)?;
-    m.add_function(wrap_pyfunction!(decode_many_to_ewkb, m)?)?;
-    m.add_function(wrap_pyfunction!(expand_geohashes, m)?)?;
-    m.add_function(wrap_pyfunction!(expand_geohash_mapping, m)?)?;
-    Ok(())
-}
+    // ── geohashes_to_multipolygon ────────────────────────────────────────────
 
-// ── Tests ─────────────────────────────────────────────────────────────────────
+    #[test]
+    fn test_dissolve_full_parent_is_its_bbox() {
+        let children = uncompact_geohash_set(&hash_set(&["f2h"]), 5, None).unwrap();
+        let mp = geohashes_to_multipolygon(&children).unwrap();
+        assert_eq!(mp.0.len(), 1);
+        assert!(mp.0[0].interiors().is_empty());
+        let expected = decode_bbox("f2h").unwrap();
+        assert_eq!(mp.bounding_rect().unwrap(), expected);
+        assert!((mp.unsigned_area() - expected.unsigned_area()).abs() < 1e-12);
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use geohash::decode_bbox;
+    #[test]
+    fn test_dissolve_ring_keeps_hole() {
+        let center = "f2h30";
+        let mut ring: HashSet<String> = all_neighbors(center).unwrap().into_iter().collect();
+        let mp = geohashes_to_multipolygon(&ring).unwrap();
+        assert_eq!(mp.0.len(), 1);
+        assert_eq!(mp.0[0].interiors().len(), 1);
+        ring.insert(center.to_string());
+        let filled = geohashes_to_multipolygon(&ring).unwrap();
+        assert!(filled.0[0].interiors().is_empty());
+    }
 
-    fn read_f64_le(buf: &[u8], offset: usize) -> f64 {
-        f64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap())
+    #[test]
+    fn test_dissolve_mixed_precision_and_disjoint() {
+        let mp = geohashes_to_multipolygon(&hash_set(&["f2h", "f2h3z", "dr5ru"])).unwrap();
+        assert_eq!(mp.0.len(), 2);
+        let expected = decode_bbox("f2h").unwrap().unsigned_area()
+            + decode_bbox("dr5ru").unwrap().unsigned_area();
+        assert!((mp.unsigned_area() - expected).abs() < 1e-12);
     }
 
-    /// Parse a WKB or EWKB bbox polygon and return (xmin, ymin, xmax, ymax).
-    /// Pass `srid: None` for plain WKB, `srid: Some(s)` to also assert the embedded SRID.
-    fn parse_polygon_bbox(buf: &[u8], srid: Option<u32>) -> (f64, f64, f64, f64) {
-        let has_srid = srid.is_some();
-        let expected_len = if has_srid { 97 } else { 93 };
-        let expected_type = if has_srid { 3u32 | 0x20000000u32 } else { 3u32 };
-        // header: byte_order(1) + type(4) [+ srid(4)] + rings(4) + points(4)
-        let coord_offset = if has_srid { 17usize } else { 13usize };
-        assert_eq!(buf.len(), expected_len);
-        assert_eq!(buf[0], 0x01, "byte order must be little-endian");
-        assert_eq!(u32::from_le_bytes(buf[1..5].try_into().unwrap()), expected_type, "WKB type mismatch");
-        let mut offset = 5;
-        if let Some(expected_srid) = srid {
-            assert_eq!(u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap()), expected_srid, "SRID mismatch");
-            offset += 4;
-        }
-        assert_eq!(u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap()), 1, "ring count must be 1");
-        assert_eq!(u32::from_le_bytes(buf[offset + 4..offset + 8].try_into().unwrap()), 5, "point count must be 5");
-        // Points: (xmin,ymin), (xmax,ymin), (xmax,ymax), (xmin,ymax), (xmin,ymin)
-        (
-            read_f64_le(buf, coord_offset),      // xmin — point 0 x
-            read_f64_le(buf, coord_offset + 8),  // ymin — point 0 y
-            read_f64_le(buf, coord_offset + 16), // xmax — point 1 x
-            read_f64_le(buf, coord_offset + 40), // ymax — point 2 y
-        )
+    #[test]
+    fn test_serialize_multipolygon_header() {
+        let mp = geohashes_to_multipolygon(&hash_set(&["f2h", "dr5ru"])).unwrap();
+        let wkb = serialize_multipolygon(&mp, Some(4326));
+        assert_eq!(wkb[0], 0x01);
+        assert_eq!(
+            u32::from_le_bytes(wkb[1..5].try_into().unwrap()),
+            6 | 0x20000000
+        );
+        assert_eq!(u32::from_le_bytes(wkb[5..9].try_into().unwrap()), 4326);
+        assert_eq!(u32::from_le_bytes(wkb[9..13].try_into().unwrap()), 2);
+        // 13-byte header followed by two 93-byte single-ring box polygons
+        assert_eq!(wkb.len(), 13 + 2 * 93);
     }
 
     // ── serialize_bbox (WKB) ─────────────────────────────────────────────────
@@ -695,7 +2765,7 @@ mod tests {
 
     // ── geohashes_to_wkb (pure Rust core) ────────────────────────────────────
 
-    fn run(geohashes: Vec<&str>, num_threads: Option<usize>) -> Vec<Result<Vec<u8>, GeohashError>> {
+    fn run(geohashes: Vec<&str>, num_threads: Option<usize>) -> Vec<Result<Vec<u8>, Error>> {
         let pool = num_threads.map(|n| rayon::ThreadPoolBuilder::new().num_threads(n).build().unwrap());
         geohashes_to_wkb(geohashes.into_iter().map(String::from).collect(), &pool)
     }
@@ -779,7 +2849,7 @@ mod tests {
         geohashes: Vec<&str>,
         srid: u32,
         num_threads: Option<usize>,
-    ) -> Vec<Result<Vec<u8>, GeohashError>> {
+    ) -> Vec<Result<Vec<u8>, Error>> {
         let pool = num_threads.map(|n| rayon::ThreadPoolBuilder::new().num_threads(n).build().unwrap());
         geohashes_to_ewkb(geohashes.into_iter().map(String::from).collect(), srid, &pool)
     }
@@ -831,6 +2901,853 @@ mod tests {
         assert_eq!(results.len(), 1);
         assert!(results[0].is_err());
     }
+
+    // ── linestrings_to_geohashes ─────────────────────────────────────────────
+
+    #[test]
+    fn test_linestring_cells_match_intersecting_cells() {
+        let line = LineString::from(vec![(-73.60, 45.50), (-73.55, 45.53), (-73.57, 45.46)]);
+        for precision in [5, 6, 7] {
+            let expected: HashSet<String> =
+                cells_in_window((-73.70, 45.40), (-73.45, 45.60), precision)
+                    .into_iter()
+                    .filter(|h| decode_bbox(h).unwrap().to_polygon().intersects(&line))
+                    .collect();
+            let got = linestrings_to_geohashes([line.clone()], precision, 0.0).unwrap();
+            assert_eq!(got, expected, "precision {precision}");
+        }
+    }
+
+    #[test]
+    fn test_linestring_through_cell_corner_visits_both_sides() {
+        // a diagonal through the shared corner of four precision-1 cells
+        let line = LineString::from(vec![(-10.0, -10.0), (10.0, 10.0)]);
+        let got = linestrings_to_geohashes([line], 1, 0.0).unwrap();
+        assert_eq!(got, hash_set(&["7", "k", "e", "s"]));
+    }
+
+    #[test]
+    fn test_linestring_degenerate_and_invalid() {
+        let point_like = LineString::from(vec![(2.35, 48.85)]);
+        let got = linestrings_to_geohashes([point_like], 6, 0.0).unwrap();
+        assert_eq!(got, hash_set(&[&encode((2.35, 48.85).into(), 6).unwrap()]));
+        let out_of_range = LineString::from(vec![(0.0, 0.0), (0.0, 95.0)]);
+        assert!(linestrings_to_geohashes([out_of_range], 6, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_linestring_corridor_buffer() {
+        let line = LineString::from(vec![(-73.60, 45.50), (-73.55, 45.53)]);
+        let bare = linestrings_to_geohashes([line.clone()], 7, 0.0).unwrap();
+        let corridor = linestrings_to_geohashes([line.clone()], 7, 200.0).unwrap();
+        assert!(corridor.is_superset(&bare));
+        let segment = line.lines().next().unwrap();
+        for h in cells_in_window((-73.62, 45.48), (-73.53, 45.55), 7) {
+            let near = segment_to_cell_m(segment, &decode_bbox(&h).unwrap()) <= 200.0;
+            assert_eq!(corridor.contains(&h), near, "{h}");
+        }
+    }
+
+    #[test]
+    fn test_lines_and_points_reject_areal_modes() {
+        let line = Geometry::LineString(LineString::from(vec![(2.30, 48.80), (2.40, 48.90)]));
+        let point = Geometry::Point(Point::new(2.35, 48.85));
+        for geometry in [line, point] {
+            for mode in [
+                CoverageMode::Contained,
+                CoverageMode::Centroid,
+                CoverageMode::MinOverlap(0.5),
+            ] {
+                assert!(matches!(
+                    geometry_to_geohashes(&geometry, 6, mode),
+                    Err(Error::InvalidArgument(_))
+                ));
+            }
+            assert!(geometry_to_geohashes(&geometry, 6, CoverageMode::Intersects).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_geometry_dispatch_points_and_collections() {
+        let point = Point::new(-73.5673, 45.5017);
+        let got = geometry_to_geohashes(&Geometry::Point(point), 7, false).unwrap();
+        assert_eq!(got, hash_set(&[&encode(point.0, 7).unwrap()]));
+
+        let multi = geo::MultiPoint::from(vec![
+            (-73.5673, 45.5017),
+            (2.3522, 48.8566),
+            (-73.5673, 45.5017),
+        ]);
+        let got = geometry_to_geohashes(&Geometry::MultiPoint(multi), 5, false).unwrap();
+        assert_eq!(got, hash_set(&["f25dv", "u09tv"]));
+
+        let polygon = montreal_pentagon();
+        let line = LineString::from(vec![(2.30, 48.80), (2.40, 48.90)]);
+        let collection = geo::GeometryCollection::new_from(vec![
+            Geometry::Polygon(polygon.clone()),
+            Geometry::LineString(line.clone()),
+            Geometry::Point(point),
+        ]);
+        let got =
+            geometry_to_geohashes(&Geometry::GeometryCollection(collection), 6, false).unwrap();
+        let mut expected = polygons_to_geohashes([polygon], 6, false).unwrap();
+        expected.extend(linestrings_to_geohashes([line], 6, 0.0).unwrap());
+        expected.insert(encode(point.0, 6).unwrap());
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn test_mixed_collection_applies_mode_to_areal_members() {
+        let polygon = montreal_pentagon();
+        let line = LineString::from(vec![(2.30, 48.80), (2.40, 48.90)]);
+        let point = Point::new(-99.1795917, 19.432134);
+        let collection = Geometry::GeometryCollection(geo::GeometryCollection::new_from(vec![
+            Geometry::Polygon(polygon.clone()),
+            Geometry::LineString(line.clone()),
+            Geometry::Point(point),
+        ]));
+        let got = geometry_to_geohashes(&collection, 6, true).unwrap();
+        let mut expected = polygons_to_geohashes([polygon], 6, true).unwrap();
+        expected.extend(linestrings_to_geohashes([line.clone()], 6, 0.0).unwrap());
+        expected.insert(encode(point.0, 6).unwrap());
+        assert_eq!(got, expected);
+        // only a point or line on its own is rejected
+        assert!(matches!(
+            geometry_to_geohashes(&Geometry::LineString(line), 6, true),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_geometry_dispatch_rect_matches_polygon() {
+        let rect = Rect::new((-73.60, 45.48), (-73.55, 45.52));
+        for mode in [CoverageMode::Contained, CoverageMode::Intersects] {
+            assert_eq!(
+                geometry_to_geohashes(&Geometry::Rect(rect), 6, mode).unwrap(),
+                polygons_to_geohashes([rect.to_polygon()], 6, mode).unwrap()
+            );
+        }
+        assert!(geometry_to_geohashes(&Geometry::Point(Point::new(0.0, 95.0)), 6, false).is_err());
+    }
+
+    fn fiji_box(lng: (f64, f64)) -> Polygon {
+        Polygon::new(
+            LineString::from(vec![
+                (lng.0, -17.0),
+                (lng.1, -17.0),
+                (lng.1, -16.0),
+                (lng.0, -16.0),
+                (lng.0, -17.0),
+            ]),
+            vec![],
+        )
+    }
+
+    /// Bounds of the cells around `fiji_box((179.0, 181.0))` at `precision`,
+    /// in continuous longitudes: cells west of the dateline are moved 360° east.
+    fn fiji_cells(precision: usize) -> HashMap<String, Rect> {
+        cells_in_window((178.5, -17.5), (181.5, -15.5), precision)
+            .into_iter()
+            .map(|h| {
+                let bbox = decode_bbox(&h).unwrap();
+                let shift = if bbox.min().x < 0.0 { 360.0 } else { 0.0 };
+                (h, bbox.translate(shift, 0.0))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_antimeridian_polygon_is_continuous() {
+        // the box is 179..181 x -17..-16, and no cell edge lies on its sides
+        let cells = fiji_cells(5);
+        let (mut contained, mut intersecting, mut centred) =
+            (HashSet::new(), HashSet::new(), HashSet::new());
+        for (h, r) in &cells {
+            let (min, max, c) = (r.min(), r.max(), r.center());
+            if min.x > 179.0 && max.x < 181.0 && min.y > -17.0 && max.y < -16.0 {
+                contained.insert(h.clone());
+            }
+            if min.x < 181.0 && max.x > 179.0 && min.y < -16.0 && max.y > -17.0 {
+                intersecting.insert(h.clone());
+            }
+            if c.x > 179.0 && c.x < 181.0 && c.y > -17.0 && c.y < -16.0 {
+                centred.insert(h.clone());
+            }
+        }
+        // cells on either side of the dateline are inside, not on the boundary
+        assert!(contained.contains(&encode((179.99, -16.5).into(), 5).unwrap()));
+        assert!(contained.contains(&encode((-179.99, -16.5).into(), 5).unwrap()));
+
+        let modes = [
+            (CoverageMode::Contained, &contained),
+            (CoverageMode::Intersects, &intersecting),
+            (CoverageMode::Centroid, &centred),
+        ];
+        for (mode, expected) in modes {
+            // wrapped ring and continuous longitudes beyond 180 give the same cells
+            for lng in [(179.0, -179.0), (179.0, 181.0), (-181.0, -179.0)] {
+                let got = polygons_to_geohashes([fiji_box(lng)], 5, mode).unwrap();
+                assert_eq!(&got, expected, "{mode:?} {lng:?}");
+            }
+        }
+
+        let weights = polygons_to_geohash_weights([fiji_box((179.0, -179.0))], 5).unwrap();
+        assert_eq!(weights.len(), intersecting.len());
+        for h in &intersecting {
+            let r = cells[h];
+            let overlap = (r.max().x.min(181.0) - r.min().x.max(179.0))
+                * (r.max().y.min(-16.0) - r.min().y.max(-17.0));
+            let fraction = overlap / (r.width() * r.height());
+            assert!((weights[h] - fraction).abs() < 1e-6, "{h}");
+        }
+        let compact =
+            polygons_to_geohashes_compact([fiji_box((179.0, -179.0))], 3, 5, true).unwrap();
+        assert_eq!(uncompact_geohash_set(&compact, 5, None).unwrap(), contained);
+    }
+
+    #[test]
+    fn test_antimeridian_polygon_with_hole() {
+        let hole = LineString::from(vec![
+            (179.5, -16.8),
+            (-179.5, -16.8),
+            (-179.5, -16.2),
+            (179.5, -16.2),
+            (179.5, -16.8),
+        ]);
+        let holed = Polygon::new(fiji_box((179.0, -179.0)).exterior().clone(), vec![hole]);
+        let pieces = split_antimeridian(&holed);
+        assert_eq!(pieces.len(), 2);
+        let area: f64 = pieces.iter().map(|p| p.unsigned_area()).sum();
+        assert!((area - (2.0 - 0.6)).abs() < 1e-6);
+        assert!(pieces
+            .iter()
+            .all(|p| p.bounding_rect().unwrap().width() <= 1.0));
+        let got = polygons_to_geohashes([holed], 6, true).unwrap();
+        assert!(!got.contains(&encode((180.0 - 1e-6, -16.5).into(), 6).unwrap()));
+        assert!(got.contains(&encode((179.2, -16.5).into(), 6).unwrap()));
+        assert!(got.contains(&encode((-179.2, -16.5).into(), 6).unwrap()));
+    }
+
+    #[test]
+    fn test_split_antimeridian_leaves_other_polygons_alone() {
+        let polygon = montreal_pentagon();
+        assert_eq!(split_antimeridian(&polygon), vec![polygon]);
+        // steps of up to 180° are not crossings, so wide polygons survive
+        let wide = Polygon::new(
+            LineString::from(vec![
+                (-170.0, -17.0),
+                (0.0, -17.0),
+                (170.0, -17.0),
+                (170.0, -16.0),
+                (0.0, -16.0),
+                (-170.0, -16.0),
+            ]),
+            vec![],
+        );
+        assert_eq!(split_antimeridian(&wide), vec![wide]);
+    }
+
+    #[test]
+    fn test_antimeridian_linestring() {
+        let crossing = LineString::from(vec![(179.5, 0.0), (-179.5, 0.5)]);
+        let got = linestrings_to_geohashes([crossing], 5, 0.0).unwrap();
+        let expected = linestrings_to_geohashes(
+            [
+                LineString::from(vec![(179.5, 0.0), (180.0, 0.25)]),
+                LineString::from(vec![(-180.0, 0.25), (-179.5, 0.5)]),
+            ],
+            5,
+            0.0,
+        )
+        .unwrap();
+        assert_eq!(got, expected);
+        assert!(got
+            .iter()
+            .all(|h| decode_bbox(h).unwrap().center().x.abs() > 179.0));
+    }
+
+    fn geodesic_to_cell(origin: Point, hash: &str) -> f64 {
+        Geodesic.distance(
+            origin,
+            closest_point_in_cell(origin, &decode_bbox(hash).unwrap()),
+        )
+    }
+
+    #[test]
+    fn test_expand_by_distance_mid_latitude() {
+        let center = encode((-73.5540, 45.5088).into(), 7).unwrap();
+        let one = hash_set(&[&center]);
+        assert_eq!(expand_geohash_set_by_distance(&one, 0.0).unwrap(), one);
+        let expanded = expand_geohash_set_by_distance(&one, 100.0).unwrap();
+        assert_eq!(expanded.len(), 9);
+        assert_eq!(expanded, expand_geohash_set(&one, 1).unwrap());
+        assert!(expand_geohash_set_by_distance(&hash_set(&["bad!"]), 10.0).is_err());
+    }
+
+    #[test]
+    fn test_expand_by_distance_near_the_poles() {
+        for (lat, precision, expansion_m) in [
+            (89.0, 4, 200_000.0),
+            (-89.0, 4, 200_000.0),
+            (89.5, 4, 30_000.0),
+            (88.5, 3, 50_000.0),
+        ] {
+            let origin = Point::new(25.0, lat);
+            let hash = encode(origin.0, precision).unwrap();
+            let expanded =
+                expand_geohash_set_by_distance(&hash_set(&[&hash]), expansion_m).unwrap();
+            // everything within reach of the cell centre is covered, across the pole too
+            let band = (lat.abs() - expansion_m / 111_000.0 * 1.5 - 0.5).copysign(lat);
+            let (south, north) = if lat > 0.0 {
+                (band, 90.0)
+            } else {
+                (-90.0, band)
+            };
+            for h in cells_in_window((-180.0, south), (180.0, north), precision) {
+                if geodesic_to_cell(origin, &h) <= expansion_m {
+                    assert!(
+                        expanded.contains(&h),
+                        "{h} missing at {lat} / {expansion_m}"
+                    );
+                }
+            }
+            // and the work stays near the pole, on the origin's hemisphere
+            assert!(expanded.iter().all(|h| {
+                let center = decode_bbox(h).unwrap().center();
+                center.y * lat > 0.0 && (center.y - lat).abs() < 4.0
+            }));
+        }
+    }
+
+    #[test]
+    fn test_polar_cap_ring_is_closed_through_the_pole() {
+        let cap = |lat: f64| {
+            Polygon::new(
+                LineString::from(vec![
+                    (0.0, lat),
+                    (90.0, lat),
+                    (180.0, lat),
+                    (-90.0, lat),
+                    (0.0, lat),
+                ]),
+                vec![],
+            )
+        };
+        let north_box = Rect::new((-180.0, 80.0), (180.0, 90.0)).to_polygon();
+        let south_box = Rect::new((-180.0, -90.0), (180.0, -80.0)).to_polygon();
+        for mode in [CoverageMode::Contained, CoverageMode::Intersects] {
+            assert_eq!(
+                polygons_to_geohashes([cap(80.0)], 3, mode).unwrap(),
+                polygons_to_geohashes([north_box.clone()], 3, mode).unwrap()
+            );
+            assert_eq!(
+                polygons_to_geohashes([cap(-80.0)], 3, mode).unwrap(),
+                polygons_to_geohashes([south_box.clone()], 3, mode).unwrap()
+            );
+        }
+        let rows = polygons_to_geohashes([cap(89.0)], 4, true).unwrap();
+        assert!(rows.iter().all(|h| decode_bbox(h).unwrap().min().y >= 89.0));
+        assert!(rows.contains(&encode((179.9, 89.5).into(), 4).unwrap()));
+        assert!(rows.contains(&encode((-179.9, 89.5).into(), 4).unwrap()));
+    }
+
+    // ── expand_geohash_set_exact ─────────────────────────────────────────────
+
+    #[test]
+    fn test_cell_to_cell_matches_sampled_boundaries() {
+        let sample = |rect: &Rect| {
+            let ring = rect.to_polygon();
+            let mut points = Vec::new();
+            for edge in ring.exterior().lines() {
+                for i in 0..=100 {
+                    let t = i as f64 / 100.0;
+                    points.push(Point::new(
+                        edge.start.x + t * edge.dx(),
+                        edge.start.y + t * edge.dy(),
+                    ));
+                }
+            }
+            points
+        };
+        let a = decode_bbox("f25dv").unwrap();
+        for other in ["f25dy", "f25g0", "f25bs", "f25ff", "dpz83", "f2h00"] {
+            let b = decode_bbox(other).unwrap();
+            let (pa, pb) = (sample(&a), sample(&b));
+            let sampled = pa
+                .iter()
+                .flat_map(|p| pb.iter().map(move |q| Haversine.distance(*p, *q)))
+                .fold(f64::INFINITY, f64::min);
+            let exact = cell_to_cell_m(&a, &b);
+            assert!(exact <= sampled + 1e-6, "{other}: {exact} > {sampled}");
+            assert!(
+                exact >= sampled - 0.001 * sampled.max(1.0),
+                "{other}: {exact} << {sampled}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_expand_exact_matches_brute_force() {
+        let geography = hash_set(&["f25dvz3", "f25dvz6", "f25dvz7", "f25dvzk", "f25dvzs"]);
+        assert_eq!(
+            expand_geohash_set_exact(&geography, 0.0).unwrap(),
+            geography
+        );
+        for expansion_m in [100.0, 450.0, 1_000.0] {
+            let exact = expand_geohash_set_exact(&geography, expansion_m).unwrap();
+            let boxes: Vec<Rect> = geography.iter().map(|h| decode_bbox(h).unwrap()).collect();
+            let expected: HashSet<String> = cells_in_window((-73.60, 45.50), (-73.54, 45.55), 7)
+                .into_iter()
+                .filter(|h| {
+                    let cell = decode_bbox(h).unwrap();
+                    boxes
+                        .iter()
+                        .any(|b| cell_to_cell_m(&cell, b) <= expansion_m)
+                })
+                .collect();
+            assert_eq!(exact, expected, "at {expansion_m} m");
+            assert!(
+                exact.is_subset(&expand_geohash_set_by_distance(&geography, expansion_m).unwrap())
+            );
+        }
+        // across the pole and the antimeridian
+        let polar = hash_set(&[&encode((179.0, 88.0).into(), 3).unwrap()]);
+        let exact = expand_geohash_set_exact(&polar, 300_000.0).unwrap();
+        let polar_box = decode_bbox(polar.iter().next().unwrap()).unwrap();
+        let expected: HashSet<String> = cells_in_window((-180.0, 80.0), (180.0, 90.0), 3)
+            .into_iter()
+            .filter(|h| cell_to_cell_m(&decode_bbox(h).unwrap(), &polar_box) <= 300_000.0)
+            .collect();
+        assert_eq!(exact, expected);
+    }
+
+    #[test]
+    fn test_expand_exact_is_round() {
+        let center = encode((-73.5540, 45.5088).into(), 7).unwrap();
+        let one = hash_set(&[&center]);
+        let square = expand_geohash_set_by_distance(&one, 2_000.0).unwrap();
+        let round = expand_geohash_set_exact(&one, 2_000.0).unwrap();
+        // the corners of the square are dropped: a disk covers ~π/4 of it
+        let ratio = round.len() as f64 / square.len() as f64;
+        assert!((0.7..0.9).contains(&ratio), "{ratio}");
+        // touching neighbours are always in, at any positive distance
+        assert_eq!(expand_geohash_set_exact(&one, 1.0).unwrap().len(), 9);
+        assert!(expand_geohash_set_exact(&hash_set(&["bad!"]), 10.0).is_err());
+    }
+
+    #[test]
+    fn test_expand_with_distance_matches_brute_force() {
+        let geography = hash_set(&["f25dvz3", "f25dvz6", "f25dvz7", "f25dvzk", "f25dvzs"]);
+        let boxes: Vec<Rect> = geography.iter().map(|h| decode_bbox(h).unwrap()).collect();
+        let distances = expand_geohash_set_with_distance(&geography, 800.0).unwrap();
+        let added: HashSet<String> = distances.keys().cloned().collect();
+        let exact = expand_geohash_set_exact(&geography, 800.0).unwrap();
+        assert_eq!(added, &exact - &geography);
+        for (h, label) in &distances {
+            let cell = decode_bbox(h).unwrap();
+            let nearest = boxes
+                .iter()
+                .map(|b| cell_to_cell_m(&cell, b))
+                .fold(f64::INFINITY, f64::min);
+            let d = label.distance_m;
+            assert!((d - nearest).abs() < 1e-9, "{h}: {d} vs {nearest}");
+            // one ring further out than the nearest labelled neighbour, or 1
+            // when touching the set
+            let neighbors = all_neighbors(h).unwrap();
+            let hops = if neighbors.iter().any(|n| geography.contains(n)) {
+                1
+            } else {
+                1 + neighbors
+                    .iter()
+                    .filter_map(|n| distances.get(n))
+                    .map(|l| l.hops)
+                    .min()
+                    .unwrap()
+            };
+            assert_eq!(label.hops, hops, "{h}");
+        }
+        assert!(expand_geohash_set_with_distance(&geography, 0.0)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_expand_mixed_matches_uncompacted() {
+        // a precision-4 cell, a touching precision-5 cell and a precision-6 cell inside the first
+        let coarse = encode((-73.60, 45.50).into(), 4).unwrap();
+        let east = neighbors(&coarse).unwrap().e;
+        let mixed = hash_set(&[&coarse, &format!("{east}0"), &format!("{coarse}00")]);
+        let flat =
+            uncompact_geohash_set(&hash_set(&[&coarse, &format!("{east}0")]), 6, None).unwrap();
+        for expand in [expand_geohash_set_by_distance, expand_geohash_set_exact] {
+            let expanded = expand_mixed_geohash_set(&mixed, 3_000.0, expand).unwrap();
+            assert!(expanded.is_superset(&mixed));
+            assert!(expanded.iter().all(|h| mixed.contains(h) || h.len() == 6));
+            assert_eq!(
+                uncompact_geohash_set(&expanded, 6, None).unwrap(),
+                expand(&flat, 3_000.0).unwrap()
+            );
+        }
+        // a single precision is expanded directly
+        let one = hash_set(&["f25dvz3"]);
+        assert_eq!(
+            expand_mixed_geohash_set(&one, 500.0, expand_geohash_set_exact).unwrap(),
+            expand_geohash_set_exact(&one, 500.0).unwrap()
+        );
+    }
+
+    // ── erosion ──────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_erode_by_distance_trims_a_block() {
+        let block: HashSet<String> = cells_in_window((-73.60, 45.50), (-73.55, 45.53), 7)
+            .into_iter()
+            .collect();
+        assert_eq!(erode_geohash_set_by_distance(&block, 0.0).unwrap(), block);
+        // cells are ~110 m wide and ~150 m tall here: 200 m takes two rings
+        let core = erode_geohash_set_by_distance(&block, 200.0).unwrap();
+        let grid = CellGrid::new(7).unwrap();
+        let index = |lng: f64, lat: f64| {
+            grid.index_of(&encode((lng, lat).into(), 7).unwrap())
+                .unwrap()
+        };
+        let ((min_col, min_row), (max_col, max_row)) = (index(-73.60, 45.50), index(-73.55, 45.53));
+        let expected: HashSet<String> = block
+            .iter()
+            .filter(|h| {
+                let (col, row) = grid.index_of(h).unwrap();
+                (min_col + 2..=max_col - 2).contains(&col)
+                    && (min_row + 2..=max_row - 2).contains(&row)
+            })
+            .cloned()
+            .collect();
+        assert_eq!(core, expected);
+        // eroding past the middle leaves nothing
+        assert!(erode_geohash_set_by_distance(&block, 5_000.0)
+            .unwrap()
+            .is_empty());
+        assert!(erode_geohash_set_by_distance(&hash_set(&["bad!"]), 10.0).is_err());
+    }
+
+    #[test]
+    fn test_erode_exact_matches_brute_force() {
+        let geography: HashSet<String> = cells_in_window((-73.59, 45.51), (-73.56, 45.53), 7)
+            .into_iter()
+            .collect();
+        let outside: Vec<Rect> = cells_in_window((-73.62, 45.49), (-73.53, 45.55), 7)
+            .into_iter()
+            .filter(|h| !geography.contains(h))
+            .map(|h| decode_bbox(&h).unwrap())
+            .collect();
+        for erosion_m in [100.0, 450.0] {
+            let core = erode_geohash_set_exact(&geography, erosion_m).unwrap();
+            let expected: HashSet<String> = geography
+                .iter()
+                .filter(|h| {
+                    let cell = decode_bbox(h).unwrap();
+                    outside.iter().all(|b| cell_to_cell_m(&cell, b) > erosion_m)
+                })
+                .cloned()
+                .collect();
+            assert_eq!(core, expected, "at {erosion_m} m");
+            assert!(
+                core.is_superset(&erode_geohash_set_by_distance(&geography, erosion_m).unwrap())
+            );
+        }
+        // eroding an expansion by the same distance gives back at least the original
+        let one = hash_set(&["f25dvz3"]);
+        let expanded = expand_geohash_set_exact(&one, 600.0).unwrap();
+        assert!(erode_geohash_set_exact(&expanded, 600.0)
+            .unwrap()
+            .is_superset(&one));
+    }
+
+    // ── set algebra ──────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_set_algebra_matches_flat_sets() {
+        let a = hash_set(&["f2h", "f2j3", "f2j3z0", "dr5r"]);
+        let b = hash_set(&["f2h0", "f2h1k", "f2j", "9q8y"]);
+        let flat = |s: &HashSet<String>| uncompact_geohash_set(s, 6, None).unwrap();
+        let (fa, fb) = (flat(&a), flat(&b));
+
+        let union = geohash_union(&a, &b).unwrap();
+        assert_eq!(flat(&union), &fa | &fb);
+        assert_eq!(union, hash_set(&["f2h", "f2j", "dr5r", "9q8y"]));
+        let intersection = geohash_intersection(&a, &b).unwrap();
+        assert_eq!(flat(&intersection), &fa & &fb);
+        assert_eq!(intersection, hash_set(&["f2h0", "f2h1k", "f2j3"]));
+        let difference = geohash_difference(&a, &b).unwrap();
+        assert_eq!(flat(&difference), &fa - &fb);
+        assert!(difference.len() < (&fa - &fb).len());
+
+        assert!(geohash_contains(&a, &hash_set(&["f2h1k", "f2hzz", "f2j3z0"])).unwrap());
+        assert!(!geohash_contains(&a, &hash_set(&["f2j"])).unwrap());
+        assert!(geohash_contains(
+            &hash_set(&["f2h"]),
+            &uncompact_geohash_set(&hash_set(&["f2h"]), 5, None).unwrap()
+        )
+        .unwrap());
+        assert!(geohash_union(&a, &hash_set(&["f2h!"])).is_err());
+    }
+
+    // ── cell hierarchy ───────────────────────────────────────────────────────
+
+    #[test]
+    fn test_parent_children_and_neighbors() {
+        assert_eq!(geohash_parent("f25dvz3", 4).unwrap(), "f25d");
+        assert_eq!(geohash_parent("f25dvz3", 7).unwrap(), "f25dvz3");
+        assert_eq!(geohash_parent("f25", 4), Err(Error::InvalidPrecision(4)));
+        assert_eq!(geohash_parent("f25", 0), Err(Error::InvalidPrecision(0)));
+
+        let children: Vec<String> = geohash_children("f25", 5).unwrap().collect();
+        assert_eq!(children.len(), 1024);
+        assert_eq!(children[..2], ["f2500".to_string(), "f2501".to_string()]);
+        assert_eq!(children.last().unwrap(), "f25zz");
+        assert!(children
+            .iter()
+            .all(|c| geohash_parent(c, 3).unwrap() == "f25"));
+        let expected = uncompact_geohash_set(&hash_set(&["f25"]), 5, None).unwrap();
+        assert_eq!(children.iter().cloned().collect::<HashSet<_>>(), expected);
+        assert!(geohash_children("f25", 3).unwrap().eq(["f25".to_string()]));
+        assert!(matches!(
+            geohash_children("f25", 2),
+            Err(Error::InvalidPrecision(2))
+        ));
+        assert!(matches!(
+            geohash_children("f25", 13),
+            Err(Error::InvalidPrecision(13))
+        ));
+        assert!(matches!(
+            geohash_children("f2a", 4),
+            Err(Error::InvalidHash(_))
+        ));
+        // the whole fan-out is never built
+        assert_eq!(
+            geohash_children("f", 12).unwrap().nth(1).unwrap(),
+            "f00000000001"
+        );
+
+        let n = neighbors("f25dvz3").unwrap();
+        let expected = [n.sw, n.s, n.se, n.w, n.e, n.nw, n.n, n.ne];
+        assert_eq!(all_neighbors("f25dvz3").unwrap(), expected);
+        // nothing beyond the pole
+        assert_eq!(
+            all_neighbors(&encode((0.0, 89.99).into(), 4).unwrap())
+                .unwrap()
+                .len(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_neighbors_do_not_wrap_over_the_poles() {
+        for lat in [89.99, -89.99] {
+            let top = encode((10.0, lat).into(), 4).unwrap();
+            let nbrs = all_neighbors(&top).unwrap();
+            assert_eq!(nbrs.len(), 5);
+            assert!(nbrs
+                .iter()
+                .all(|h| decode_bbox(h).unwrap().center().y * lat > 0.0));
+        }
+        let expanded =
+            expand_geohash_set(&hash_set(&[&encode((10.0, 89.99).into(), 3).unwrap()]), 3).unwrap();
+        assert!(expanded
+            .iter()
+            .all(|h| decode_bbox(h).unwrap().center().y > 80.0));
+        // across the antimeridian wrapping still applies
+        let east = encode((179.99, 0.0).into(), 4).unwrap();
+        assert!(all_neighbors(&east)
+            .unwrap()
+            .contains(&encode((-179.99, 0.0).into(), 4).unwrap()));
+    }
+
+    // ── k-rings ──────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_k_ring_and_ring() {
+        let h = "f25dvz3";
+        assert_eq!(geohash_k_ring(h, 0, None).unwrap(), vec![h.to_string()]);
+        assert_eq!(geohash_ring(h, 0, None).unwrap(), vec![h.to_string()]);
+        let one: HashSet<String> = geohash_k_ring(h, 1, None).unwrap().into_iter().collect();
+        assert_eq!(one, expand_geohash_set(&hash_set(&[h]), 1).unwrap());
+        for k in 1..4 {
+            let ring: HashSet<String> = geohash_ring(h, k, None).unwrap().into_iter().collect();
+            let inner: HashSet<String> = geohash_k_ring(h, k - 1, None)
+                .unwrap()
+                .into_iter()
+                .collect();
+            let outer: HashSet<String> = geohash_k_ring(h, k, None).unwrap().into_iter().collect();
+            assert_eq!(ring.len(), 8 * k);
+            assert_eq!(ring, &outer - &inner);
+            assert_eq!(outer, expand_geohash_set(&hash_set(&[h]), k).unwrap());
+        }
+        // clipped at the pole, and wrapped whole around it at precision 1
+        assert_eq!(
+            geohash_ring(&encode((0.0, 89.99).into(), 4).unwrap(), 1, None)
+                .unwrap()
+                .len(),
+            5
+        );
+        let everything = geohash_k_ring("u", 10, None).unwrap();
+        assert_eq!(everything.len(), 32);
+        // 8 columns: only the opposite one is 4 steps away, on every row
+        assert_eq!(geohash_ring("u", 4, None).unwrap().len(), 4);
+        assert!(geohash_k_ring("f2a", 1, None).is_err());
+    }
+
+    #[test]
+    fn test_k_ring_size_is_bounded() {
+        let h = "f25dvz3";
+        assert_eq!(geohash_k_ring(h, 3, Some(49)).unwrap().len(), 49);
+        assert_eq!(geohash_ring(h, 3, Some(24)).unwrap().len(), 24);
+        for (k, max_cells) in [(3, 48), (1_000, 1_000_000)] {
+            assert!(matches!(
+                geohash_k_ring(h, k, Some(max_cells)),
+                Err(Error::TooManyCells { limit }) if limit == max_cells
+            ));
+        }
+        assert!(matches!(
+            geohash_ring(h, 3, Some(23)),
+            Err(Error::TooManyCells { limit: 23 })
+        ));
+        // the count matches what is built, clipped at a pole and wrapped whole
+        for build in [geohash_k_ring, geohash_ring] {
+            for (hash, k) in [("u", 4), ("u", 10), ("b", 2), ("zzz", 2), ("bp", 5)] {
+                let n = build(hash, k, None).unwrap().len();
+                assert!(build(hash, k, Some(n)).is_ok());
+                assert!(n == 0 || build(hash, k, Some(n - 1)).is_err(), "{hash} {k}");
+            }
+        }
+    }
+
+    // ── PackedGeohash ────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_packed_geohash_matches_strings() {
+        let points = [
+            (-73.5540, 45.5088),
+            (179.99, -89.99),
+            (-180.0, 0.0),
+            (0.0, 0.0),
+            (139.69, 35.68),
+        ];
+        for (lng, lat) in points {
+            for precision in 1..=12 {
+                let packed = PackedGeohash::encode((lng, lat).into(), precision).unwrap();
+                let hash = encode((lng, lat).into(), precision).unwrap();
+                assert_eq!(packed.to_string(), hash);
+                assert_eq!(hash.parse::<PackedGeohash>().unwrap(), packed);
+                assert_eq!(packed.precision(), precision);
+                assert_eq!(packed.bbox(), decode_bbox(&hash).unwrap());
+                assert_eq!(PackedGeohash::try_from(packed.as_u64()).unwrap(), packed);
+                assert!(packed.as_u64() < 1 << 61);
+                if precision > 1 {
+                    assert_eq!(
+                        packed.as_u64() >> 5,
+                        hash[..precision - 1]
+                            .parse::<PackedGeohash>()
+                            .unwrap()
+                            .as_u64()
+                    );
+                }
+            }
+        }
+        // descendants are a contiguous range
+        let parent: PackedGeohash = "f25".parse().unwrap();
+        let range = (parent.as_u64() << 10)..((parent.as_u64() + 1) << 10);
+        for child in geohash_children("f25", 5).unwrap() {
+            assert!(range.contains(&child.parse::<PackedGeohash>().unwrap().as_u64()));
+        }
+
+        for bad in [0u64, 1, 31, 1 << 7, 1 << 61] {
+            assert!(PackedGeohash::try_from(bad).is_err(), "{bad}");
+        }
+        assert!("f2a".parse::<PackedGeohash>().is_err());
+        assert!(PackedGeohash::encode((0.0, 91.0).into(), 5).is_err());
+        assert!(PackedGeohash::encode((0.0, 0.0).into(), 13).is_err());
+    }
+
+    #[test]
+    fn test_packed_grid_index_round_trips() {
+        for precision in 1..=12 {
+            let grid = CellGrid::new(precision).unwrap();
+            for (col, row) in [
+                (0, 0),
+                (grid.cols - 1, grid.rows - 1),
+                (grid.cols / 3, grid.rows / 7),
+            ] {
+                let packed = grid.packed_at(col, row);
+                assert_eq!(packed.index(), (col, row));
+                assert_eq!(packed.to_string(), grid.hash_at(col, row).unwrap());
+            }
+            // columns wrap across the antimeridian
+            assert_eq!(grid.packed_at(-1, 0), grid.packed_at(grid.cols - 1, 0));
+        }
+        for hash in ["f25dvz3", "zzzz", "0000", "b", "pbpbpbpbpbpb"] {
+            let grid = CellGrid::of_hash(hash).unwrap();
+            let packed: PackedGeohash = hash.parse().unwrap();
+            let neighbors: Vec<String> = grid
+                .packed_neighbors(packed)
+                .map(|h| h.to_string())
+                .collect();
+            assert_eq!(neighbors, all_neighbors(hash).unwrap(), "{hash}");
+        }
+    }
+
+    #[test]
+    fn test_packed_coverage_and_expansion_match_strings() {
+        let modes = [
+            CoverageMode::Contained,
+            CoverageMode::Intersects,
+            CoverageMode::Centroid,
+            CoverageMode::MinOverlap(0.5),
+        ];
+        let shapes = [
+            vec![montreal_pentagon()],
+            vec![fiji_box((179.0, -179.0))],
+            vec![fiji_box((170.0, 170.3)), fiji_box((170.3, 170.5))],
+        ];
+        let to_strings = |set: HashSet<PackedGeohash>| -> HashSet<String> {
+            set.iter().map(PackedGeohash::to_string).collect()
+        };
+        for polygons in &shapes {
+            for mode in modes {
+                let packed = polygons_to_packed_geohashes(polygons.clone(), 5, mode).unwrap();
+                let strings = polygons_to_geohashes(polygons.clone(), 5, mode).unwrap();
+                assert!(!strings.is_empty());
+                assert_eq!(to_strings(packed), strings, "{mode:?}");
+            }
+        }
+        let err = polygons_to_packed_geohashes_bounded(
+            shapes[0].clone(),
+            7,
+            false,
+            Some(10),
+            &mut || false,
+        );
+        assert_eq!(err, Err(Error::TooManyCells { limit: 10 }));
+
+        // across the antimeridian and into the polar rows
+        let sources = [
+            polygons_to_geohashes(shapes[1].clone(), 5, false).unwrap(),
+            hash_set(&["f25dv", "f25dy", "9q8yy"]),
+            HashSet::from([encode((30.0, 89.9).into(), 4).unwrap()]),
+        ];
+        for source in &sources {
+            let packed: HashSet<PackedGeohash> =
+                source.iter().map(|h| h.parse().unwrap()).collect();
+            for expansion_m in [0.0, 800.0, 25_000.0] {
+                let expanded = expand_packed_geohash_set_by_distance(&packed, expansion_m).unwrap();
+                assert!(expanded.is_superset(&packed));
+                assert_eq!(
+                    to_strings(expanded),
+                    expand_geohash_set_by_distance(source, expansion_m).unwrap(),
+                    "{expansion_m}"
+                );
+            }
+        }
+    }
 }
 
 // ── Interior seed (existing) ──────────────────────────────────────────────────
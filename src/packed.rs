@@ -0,0 +1,167 @@
+//! Geohashes packed into integers, for compact storage and cheap comparisons.
+
+use crate::{validate_geohash, Error, BASE32};
+use geo::{Coord, Rect};
+use std::fmt;
+use std::str::FromStr;
+
+/// A geohash packed into a `u64`: the 5 bits of each character behind a
+/// leading 1 that marks the precision.
+///
+/// A precision-`p` cell is `(1 << 5p) | bits`, at most 61 bits, so values fit
+/// a signed BIGINT column and differ between precisions. A cell's parent is
+/// its value shifted right by 5, and its descendants at any finer precision
+/// form a contiguous range of values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PackedGeohash(u64);
+
+impl PackedGeohash {
+    /// The cell of `precision` containing `coord`, as `geohash::encode` finds it.
+    pub fn encode(coord: Coord, precision: usize) -> Result<Self, Error> {
+        if !(-180.0..=180.0).contains(&coord.x) || !(-90.0..=90.0).contains(&coord.y) {
+            return Err(Error::InvalidCoordinate {
+                lng: coord.x,
+                lat: coord.y,
+            });
+        }
+        if precision == 0 || precision > 12 {
+            return Err(Error::InvalidPrecision(precision));
+        }
+        // Same fixed-point trick as the geohash crate: mapping each axis onto
+        // [1, 2) leaves its 32 leading binary digits in the mantissa.
+        let lat32 = ((coord.y * 0.005555555555555556 + 1.5).to_bits() >> 20) as u32;
+        let lng32 = ((coord.x * 0.002777777777777778 + 1.5).to_bits() >> 20) as u32;
+        Ok(PackedGeohash::interleave(lng32, lat32, precision))
+    }
+
+    /// The cell of `precision` in grid column `col` and row `row`, counted from
+    /// the south-west corner; both must be on the grid.
+    pub(crate) fn from_index(col: i64, row: i64, precision: usize) -> Self {
+        let total_bits = 5 * precision as u32;
+        let (lat_bits, lng_bits) = (total_bits / 2, total_bits - total_bits / 2);
+        let lng32 = (col as u32) << (32 - lng_bits);
+        let lat32 = (row as u32) << (32 - lat_bits);
+        PackedGeohash::interleave(lng32, lat32, precision)
+    }
+
+    /// Grid column and row of the cell, the inverse of `from_index`.
+    pub(crate) fn index(self) -> (i64, i64) {
+        let total_bits = 5 * self.precision() as u32;
+        let (lat_bits, lng_bits) = (total_bits / 2, total_bits - total_bits / 2);
+        let (lat32, lng32) = self.deinterleave();
+        let (col, row) = (lng32 >> (32 - lng_bits), lat32 >> (32 - lat_bits));
+        (col as i64, row as i64)
+    }
+
+    /// Pack the leading `5 * precision` bits of two 32-bit fixed-point axes.
+    fn interleave(lng32: u32, lat32: u32, precision: usize) -> Self {
+        let interleaved = spread(lat32) | (spread(lng32) << 1);
+        let bits = interleaved >> (64 - 5 * precision);
+        PackedGeohash((1 << (5 * precision)) | bits)
+    }
+
+    /// The latitude and longitude bits of the cell, each left-aligned in a
+    /// `u32`; the inverse of `interleave`.
+    fn deinterleave(self) -> (u32, u32) {
+        let aligned = self.bits() << (64 - 5 * self.precision());
+        (squash(aligned), squash(aligned >> 1))
+    }
+
+    /// The packed value.
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+
+    /// Number of geohash characters, 1 to 12.
+    pub fn precision(self) -> usize {
+        (63 - self.0.leading_zeros() as usize) / 5
+    }
+
+    /// The `5 * precision` geohash bits, without the precision marker.
+    pub fn bits(self) -> u64 {
+        self.0 ^ (1 << (5 * self.precision()))
+    }
+
+    /// Bounding box of the cell.
+    pub fn bbox(self) -> Rect {
+        let total_bits = 5 * self.precision() as u32;
+        let (lat_bits, lng_bits) = (total_bits / 2, total_bits - total_bits / 2);
+        let (lat32, lng32) = self.deinterleave();
+        let south = lat32 as f64 / 2f64.powi(32) * 180.0 - 90.0;
+        let west = lng32 as f64 / 2f64.powi(32) * 360.0 - 180.0;
+        let lat_err = 180.0 / 2f64.powi(lat_bits as i32);
+        let lng_err = 360.0 / 2f64.powi(lng_bits as i32);
+        Rect::new(
+            Coord { x: west, y: south },
+            Coord {
+                x: west + lng_err,
+                y: south + lat_err,
+            },
+        )
+    }
+}
+
+impl TryFrom<u64> for PackedGeohash {
+    type Error = Error;
+
+    fn try_from(value: u64) -> Result<Self, Error> {
+        // position of the precision marker: 5 to 60, in steps of 5
+        let marker = 63 - value.leading_zeros().min(63);
+        if !marker.is_multiple_of(5) || !(5..=60).contains(&marker) {
+            return Err(Error::InvalidHash(format!(
+                "{value} is not a packed geohash"
+            )));
+        }
+        Ok(PackedGeohash(value))
+    }
+}
+
+impl From<PackedGeohash> for u64 {
+    fn from(hash: PackedGeohash) -> u64 {
+        hash.0
+    }
+}
+
+impl FromStr for PackedGeohash {
+    type Err = Error;
+
+    fn from_str(hash: &str) -> Result<Self, Error> {
+        validate_geohash(hash)?;
+        let bits = hash.bytes().fold(0u64, |bits, c| {
+            let value = BASE32.iter().position(|&b| b == c).unwrap_or_default();
+            (bits << 5) | value as u64
+        });
+        Ok(PackedGeohash((1 << (5 * hash.len())) | bits))
+    }
+}
+
+impl fmt::Display for PackedGeohash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (precision, bits) = (self.precision(), self.bits());
+        for i in (0..precision).rev() {
+            let code = (bits >> (5 * i)) & 0x1f;
+            write!(f, "{}", BASE32[code as usize] as char)?;
+        }
+        Ok(())
+    }
+}
+
+/// Deposit the bits of `x` into the even bit positions of a `u64`.
+fn spread(x: u32) -> u64 {
+    let mut x = x as u64;
+    x = (x | (x << 16)) & 0x0000_ffff_0000_ffff;
+    x = (x | (x << 8)) & 0x00ff_00ff_00ff_00ff;
+    x = (x | (x << 4)) & 0x0f0f_0f0f_0f0f_0f0f;
+    x = (x | (x << 2)) & 0x3333_3333_3333_3333;
+    (x | (x << 1)) & 0x5555_5555_5555_5555
+}
+
+/// Gather the even bit positions of `x` into a `u32`; the inverse of `spread`.
+fn squash(x: u64) -> u32 {
+    let mut x = x & 0x5555_5555_5555_5555;
+    x = (x | (x >> 1)) & 0x3333_3333_3333_3333;
+    x = (x | (x >> 2)) & 0x0f0f_0f0f_0f0f_0f0f;
+    x = (x | (x >> 4)) & 0x00ff_00ff_00ff_00ff;
+    x = (x | (x >> 8)) & 0x0000_ffff_0000_ffff;
+    (x | (x >> 16)) as u32
+}
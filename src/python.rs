@@ -0,0 +1,1865 @@
+//! PyO3 bindings, built with the `python` feature.
+
+use crate::*;
+use geo::MultiLineString;
+use pyo3::buffer::PyBuffer;
+use pyo3::prelude::*;
+use pyo3::types::{PyAny, PyDict};
+use pyo3::wrap_pyfunction;
+
+// ── Exceptions ───────────────────────────────────────────────────────────────
+
+// Base class of every error raised from a crate `Error`. It subclasses
+// `ValueError`, so code catching `ValueError` keeps working.
+pyo3::create_exception!(
+    geohash_polygon,
+    GeohashError,
+    pyo3::exceptions::PyValueError,
+    "Base class for geohash_polygon errors."
+);
+pyo3::create_exception!(
+    geohash_polygon,
+    InvalidGeohashError,
+    GeohashError,
+    "A geohash is malformed."
+);
+pyo3::create_exception!(
+    geohash_polygon,
+    InvalidPrecisionError,
+    GeohashError,
+    "A precision is outside 1..=12."
+);
+pyo3::create_exception!(
+    geohash_polygon,
+    InvalidCoordinateError,
+    GeohashError,
+    "A coordinate is outside the valid longitude/latitude range."
+);
+pyo3::create_exception!(
+    geohash_polygon,
+    MixedPrecisionError,
+    GeohashError,
+    "Geohashes that must share a precision do not."
+);
+pyo3::create_exception!(
+    geohash_polygon,
+    InvalidGeometryError,
+    GeohashError,
+    "A geometry is unsupported or malformed."
+);
+pyo3::create_exception!(
+    geohash_polygon,
+    TooManyCellsError,
+    GeohashError,
+    "The result would exceed max_cells."
+);
+
+impl From<Error> for PyErr {
+    fn from(e: Error) -> Self {
+        let msg = e.to_string();
+        match e {
+            Error::InvalidHash(_) => InvalidGeohashError::new_err(msg),
+            Error::InvalidPrecision(_) => InvalidPrecisionError::new_err(msg),
+            Error::InvalidCoordinate { .. } => InvalidCoordinateError::new_err(msg),
+            Error::MixedPrecision => MixedPrecisionError::new_err(msg),
+            Error::InvalidGeometry(_) => InvalidGeometryError::new_err(msg),
+            Error::TooManyCells { .. } => TooManyCellsError::new_err(msg),
+            Error::Interrupted => pyo3::exceptions::PyKeyboardInterrupt::new_err(msg),
+            Error::InvalidArgument(_) => GeohashError::new_err(msg),
+        }
+    }
+}
+
+/// Raise `InvalidGeometryError` with `msg`.
+fn invalid_geometry(msg: impl Into<String>) -> PyErr {
+    Error::InvalidGeometry(msg.into()).into()
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// Build a custom thread pool, or return `None` to use the global Rayon pool.
+///
+/// Call this *before* releasing the GIL so that pool-creation errors can be
+/// converted to Python exceptions while we still hold it.
+fn make_pool(num_threads: Option<usize>) -> PyResult<Option<rayon::ThreadPool>> {
+    match num_threads {
+        None => Ok(None),
+        Some(n) => rayon::ThreadPoolBuilder::new()
+            .num_threads(n)
+            .build()
+            .map(Some)
+            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string())),
+    }
+}
+
+/// Run a bounded walk, stopping it when a signal such as Ctrl-C is pending.
+///
+/// The walk keeps the GIL so pending signals can be checked as it goes; the
+/// signal's own exception (usually KeyboardInterrupt) is the one raised.
+fn with_signal_checks<T>(
+    py: Python<'_>,
+    walk: impl FnOnce(&mut dyn FnMut() -> bool) -> Result<T, Error>,
+) -> PyResult<T> {
+    let mut signal = None;
+    let mut interrupted = || match py.check_signals() {
+        Ok(()) => false,
+        Err(e) => {
+            signal = Some(e);
+            true
+        }
+    };
+    let result = walk(&mut interrupted);
+    result.map_err(|e| signal.take().unwrap_or_else(|| e.into()))
+}
+
+// ── Geometry extraction ──────────────────────────────────────────────────────
+
+/// Walk a `__geo_interface__` coordinate ring (list of [x, y] pairs) into a LineString.
+fn extract_ring(ring: &Bound<'_, PyAny>) -> PyResult<geo_types::LineString<f64>> {
+    let mut coords = Vec::new();
+    for (i, item) in ring.try_iter()?.enumerate() {
+        coords.push(extract_coord(&item?).map_err(|_| {
+            invalid_geometry(format!(
+                "invalid coordinate at index {i}: expected [longitude, latitude]"
+            ))
+        })?);
+    }
+    Ok(geo_types::LineString::new(coords))
+}
+
+/// Read a single `[longitude, latitude]` pair.
+fn extract_coord(pair: &Bound<'_, PyAny>) -> PyResult<geo_types::Coord<f64>> {
+    Ok(geo_types::Coord {
+        x: pair.get_item(0)?.extract()?,
+        y: pair.get_item(1)?.extract()?,
+    })
+}
+
+/// Build a `Polygon` from a `__geo_interface__` coordinates value (list of rings).
+fn extract_polygon(coordinates: &Bound<'_, PyAny>) -> PyResult<Polygon<f64>> {
+    let mut iter = coordinates.try_iter()?;
+    let exterior = extract_ring(
+        &iter
+            .next()
+            .ok_or_else(|| invalid_geometry("Polygon has no rings"))??,
+    )?;
+    let holes = iter
+        .map(|r| -> PyResult<_> { extract_ring(&r?) })
+        .collect::<PyResult<Vec<_>>>()?;
+    Ok(Polygon::new(exterior, holes))
+}
+
+/// Build a `Vec<Polygon>` from a `__geo_interface__` MultiPolygon coordinates value.
+fn extract_multipolygon(coordinates: &Bound<'_, PyAny>) -> PyResult<Vec<Polygon<f64>>> {
+    coordinates
+        .try_iter()?
+        .map(|item| -> PyResult<_> { extract_polygon(&item?) })
+        .collect()
+}
+
+/// Read a Shapely geometry into a `Geometry` via `__geo_interface__`.
+fn extract_geometry(py_geometry: &Bound<'_, PyAny>) -> PyResult<Geometry<f64>> {
+    let geo_interface = py_geometry.getattr("__geo_interface__").map_err(|_| {
+        invalid_geometry(
+            "Object does not implement __geo_interface__. Expected a Shapely geometry.",
+        )
+    })?;
+    geometry_from_geo_interface(&geo_interface)
+}
+
+/// Build a `Geometry` from a GeoJSON-like `__geo_interface__` mapping.
+fn geometry_from_geo_interface(geo_interface: &Bound<'_, PyAny>) -> PyResult<Geometry<f64>> {
+    let geom_type: String = geo_interface
+        .get_item("type")
+        .map_err(|_| {
+            invalid_geometry("__geo_interface__ mapping is missing the required 'type' key")
+        })?
+        .extract()
+        .map_err(|_| invalid_geometry("__geo_interface__ 'type' value must be a string"))?;
+
+    if geom_type == "GeometryCollection" {
+        let members = geo_interface.get_item("geometries").map_err(|_| {
+            invalid_geometry("__geo_interface__ mapping is missing the required 'geometries' key")
+        })?;
+        return Ok(Geometry::GeometryCollection(
+            members
+                .try_iter()?
+                .map(|item| -> PyResult<_> { geometry_from_geo_interface(&item?) })
+                .collect::<PyResult<_>>()?,
+        ));
+    }
+
+    let coordinates = geo_interface.get_item("coordinates").map_err(|_| {
+        invalid_geometry("__geo_interface__ mapping is missing the required 'coordinates' key")
+    })?;
+
+    match geom_type.as_str() {
+        "Point" => {
+            let coord = extract_coord(&coordinates).map_err(|_| {
+                invalid_geometry("invalid Point coordinates: expected [longitude, latitude]")
+            })?;
+            Ok(Geometry::Point(Point(coord)))
+        }
+        "MultiPoint" => Ok(Geometry::MultiPoint(
+            extract_ring(&coordinates)?.into_points().into(),
+        )),
+        "Polygon" => Ok(Geometry::Polygon(extract_polygon(&coordinates)?)),
+        "MultiPolygon" => Ok(Geometry::MultiPolygon(MultiPolygon::new(
+            extract_multipolygon(&coordinates)?,
+        ))),
+        "LineString" => Ok(Geometry::LineString(extract_ring(&coordinates)?)),
+        "MultiLineString" => Ok(Geometry::MultiLineString(MultiLineString::new(
+            coordinates
+                .try_iter()?
+                .map(|item| -> PyResult<_> { extract_ring(&item?) })
+                .collect::<PyResult<_>>()?,
+        ))),
+        other => Err(invalid_geometry(format!(
+            "Unsupported geometry type: {other}"
+        ))),
+    }
+}
+
+/// Extract the polygons of a Shapely Polygon or MultiPolygon via `__geo_interface__`.
+fn extract_polygons(py_polygon: &Bound<'_, PyAny>) -> PyResult<Vec<Polygon<f64>>> {
+    match extract_geometry(py_polygon)? {
+        Geometry::Polygon(polygon) => Ok(vec![polygon]),
+        Geometry::MultiPolygon(mp) => Ok(mp.0),
+        _ => Err(invalid_geometry(
+            "The geometry is not a Polygon or MultiPolygon",
+        )),
+    }
+}
+
+/// Extract the lines of a Shapely LineString or MultiLineString via `__geo_interface__`.
+fn extract_linestrings(py_line: &Bound<'_, PyAny>) -> PyResult<Vec<LineString<f64>>> {
+    match extract_geometry(py_line)? {
+        Geometry::LineString(line) => Ok(vec![line]),
+        Geometry::MultiLineString(mls) => Ok(mls.0),
+        _ => Err(invalid_geometry(
+            "The geometry is not a LineString or MultiLineString",
+        )),
+    }
+}
+
+// ── Polygon → geohash ────────────────────────────────────────────────────────
+
+/// Resolve the `inner` / `mode` / `min_overlap` arguments of the Python API.
+fn parse_coverage_mode(
+    inner: Option<bool>,
+    mode: Option<&str>,
+    min_overlap: Option<f64>,
+) -> PyResult<CoverageMode> {
+    let err = |msg: &str| Err(Error::InvalidArgument(msg.to_string()).into());
+    match (inner, mode, min_overlap) {
+        (Some(_), Some(_), _) => err("pass either inner or mode, not both"),
+        (None, None, _) => err("one of inner or mode is required"),
+        (Some(inner), None, None) => Ok(inner.into()),
+        (None, Some("contained"), None) => Ok(CoverageMode::Contained),
+        (None, Some("intersects"), None) => Ok(CoverageMode::Intersects),
+        (None, Some("centroid"), None) => Ok(CoverageMode::Centroid),
+        (None, Some("min_overlap"), Some(f)) if (0.0..=1.0).contains(&f) => {
+            Ok(CoverageMode::MinOverlap(f))
+        }
+        (None, Some("min_overlap"), _) => err("min_overlap must be a number between 0 and 1"),
+        (_, Some("contained" | "intersects" | "centroid") | None, Some(_)) => {
+            err("min_overlap is only valid with mode='min_overlap'")
+        }
+        (None, Some(other), _) => Err(Error::InvalidArgument(format!(
+            "unknown mode {other:?}: expected 'contained', 'intersects', 'centroid' or 'min_overlap'"
+        ))
+        .into()),
+    }
+}
+
+/// Whether `geometry` has an area for `inner` / `mode` to apply to.
+fn has_area(geometry: &Geometry<f64>) -> bool {
+    match geometry {
+        Geometry::Polygon(_)
+        | Geometry::MultiPolygon(_)
+        | Geometry::Rect(_)
+        | Geometry::Triangle(_) => true,
+        Geometry::GeometryCollection(collection) => collection.iter().any(has_area),
+        _ => false,
+    }
+}
+
+/// Convert a Shapely geometry to the set of geohashes covering it.
+///
+/// For a Polygon or MultiPolygon, pass either `inner` (fully contained cells when
+/// `True`, intersecting cells when `False`) or `mode`, one of `'contained'`,
+/// `'intersects'`, `'centroid'` (cell centre inside the polygon) or
+/// `'min_overlap'` (at least `min_overlap`, a fraction between 0 and 1, of the
+/// cell's area inside the polygon). Lines map to the cells they pass through and
+/// points to their containing cell; they need no `inner` or `mode`, and any mode
+/// other than intersecting raises `ValueError`. A GeometryCollection is the
+/// union of its members.
+///
+/// With `max_cells`, raises `TooManyCellsError` as soon as the result would hold
+/// more cells than that. Ctrl-C interrupts a long coverage.
+#[pyfunction]
+#[pyo3(signature = (py_polygon, precision, inner=None, *, mode=None, min_overlap=None, max_cells=None))]
+fn polygon_to_geohashes(
+    py: Python,
+    py_polygon: Bound<'_, PyAny>,
+    precision: usize,
+    inner: Option<bool>,
+    mode: Option<&str>,
+    min_overlap: Option<f64>,
+    max_cells: Option<usize>,
+) -> PyResult<HashSet<String>> {
+    let geometry = extract_geometry(&py_polygon)?;
+    let mode = geometry_coverage_mode(&geometry, inner, mode, min_overlap)?;
+    with_signal_checks(py, |interrupted| {
+        geometry_to_geohashes_bounded(&geometry, precision, mode, max_cells, interrupted)
+    })
+}
+
+/// The coverage mode for `geometry`: lines and points need none, so they
+/// default to intersecting.
+fn geometry_coverage_mode(
+    geometry: &Geometry<f64>,
+    inner: Option<bool>,
+    mode: Option<&str>,
+    min_overlap: Option<f64>,
+) -> PyResult<CoverageMode> {
+    match (inner, mode, min_overlap) {
+        (None, None, None) if !has_area(geometry) => Ok(CoverageMode::Intersects),
+        _ => parse_coverage_mode(inner, mode, min_overlap),
+    }
+}
+
+/// Geohashes a Shapely LineString or MultiLineString passes through.
+///
+/// Lines are walked cell by cell. With a positive `buffer_m`, every cell within
+/// that many metres of the line is included as well, giving a corridor.
+/// `max_cells` and Ctrl-C work as in `polygon_to_geohashes`.
+#[pyfunction]
+#[pyo3(signature = (line, precision, buffer_m=0.0, *, max_cells=None))]
+fn linestring_to_geohashes(
+    py: Python<'_>,
+    line: Bound<'_, PyAny>,
+    precision: usize,
+    buffer_m: f64,
+    max_cells: Option<usize>,
+) -> PyResult<HashSet<String>> {
+    if !buffer_m.is_finite() || buffer_m < 0.0 {
+        return Err(
+            Error::InvalidArgument("buffer_m must be a finite non-negative number".into()).into(),
+        );
+    }
+    let lines = extract_linestrings(&line)?;
+    with_signal_checks(py, |interrupted| {
+        linestrings_to_geohashes_bounded(lines, precision, buffer_m, max_cells, interrupted)
+    })
+}
+
+/// Convert a list of Shapely Polygons or MultiPolygons to geohash sets (parallel).
+///
+/// Output order matches input order — `result[i]` is the geohash set of
+/// `polygons[i]`. Geometries are read while holding the GIL, then covered in
+/// parallel across geographies via Rayon.
+#[pyfunction]
+#[pyo3(signature = (polygons, precision, inner, num_threads=None))]
+fn polygons_to_geohash_mapping(
+    py: Python<'_>,
+    polygons: Vec<Bound<'_, PyAny>>,
+    precision: usize,
+    inner: bool,
+    num_threads: Option<usize>,
+) -> PyResult<Vec<HashSet<String>>> {
+    let groups = polygons
+        .iter()
+        .map(extract_polygons)
+        .collect::<PyResult<Vec<_>>>()?;
+    let pool = make_pool(num_threads)?;
+    let raw = py.allow_threads(|| polygon_groups_to_geohashes(groups, precision, inner, &pool));
+    raw.into_iter().map(|r| r.map_err(PyErr::from)).collect()
+}
+
+/// Map every geohash intersecting a Shapely Polygon or MultiPolygon to the
+/// fraction of its area inside the geometry (0..1].
+#[pyfunction]
+fn polygon_to_geohash_weights(
+    py: Python<'_>,
+    py_polygon: Bound<'_, PyAny>,
+    precision: usize,
+) -> PyResult<HashMap<String, f64>> {
+    let polygons = extract_polygons(&py_polygon)?;
+    py.allow_threads(|| polygons_to_geohash_weights(polygons, precision))
+        .map_err(PyErr::from)
+}
+
+/// Cover a Shapely Polygon or MultiPolygon with a mixed-precision geohash set.
+///
+/// Interior cells use the coarsest fully-contained precision down from
+/// `min_precision`; boundary cells are refined to `max_precision`. `max_cells`
+/// and Ctrl-C work as in `polygon_to_geohashes`.
+#[pyfunction]
+#[pyo3(signature = (py_polygon, min_precision, max_precision, inner, *, max_cells=None))]
+fn polygon_to_geohashes_compact(
+    py: Python<'_>,
+    py_polygon: Bound<'_, PyAny>,
+    min_precision: usize,
+    max_precision: usize,
+    inner: bool,
+    max_cells: Option<usize>,
+) -> PyResult<HashSet<String>> {
+    let polygons = extract_polygons(&py_polygon)?;
+    with_signal_checks(py, |interrupted| {
+        polygons_to_geohashes_compact_bounded(
+            polygons,
+            min_precision,
+            max_precision,
+            inner,
+            max_cells,
+            interrupted,
+        )
+    })
+}
+
+fn check_precision_targets(max_cells: Option<usize>, target_cell_m: Option<f64>) -> PyResult<()> {
+    if max_cells.is_none() && target_cell_m.is_none() {
+        return Err(
+            Error::InvalidArgument("one of max_cells or target_cell_m is required".into()).into(),
+        );
+    }
+    if target_cell_m.is_some_and(|m| !m.is_finite() || m <= 0.0) {
+        return Err(Error::InvalidArgument(
+            "target_cell_m must be a finite positive number".into(),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Finest precision at which a Shapely Polygon or MultiPolygon fits the targets.
+///
+/// With `max_cells`, the estimated number of covering cells (from the area and
+/// perimeter of the geometry) must not exceed it; with `target_cell_m`, cells
+/// must be at least that many metres across at the geometry's latitude. Raises
+/// `TooManyCellsError` if even precision 1 needs more than `max_cells` cells.
+#[pyfunction]
+#[pyo3(name = "choose_precision", signature = (polygon, max_cells=None, target_cell_m=None))]
+fn choose_precision_py(
+    polygon: Bound<'_, PyAny>,
+    max_cells: Option<usize>,
+    target_cell_m: Option<f64>,
+) -> PyResult<usize> {
+    check_precision_targets(max_cells, target_cell_m)?;
+    let polygons = extract_polygons(&polygon)?;
+    Ok(choose_precision(&polygons, max_cells, target_cell_m)?)
+}
+
+/// `polygon_to_geohashes` at the precision picked by `choose_precision`.
+///
+/// `max_cells` also bounds the covering, and Ctrl-C works, as in
+/// `polygon_to_geohashes`.
+#[pyfunction]
+#[pyo3(signature = (
+    polygon, inner=None, *, mode=None, min_overlap=None, max_cells=None, target_cell_m=None
+))]
+fn polygon_to_geohashes_auto(
+    py: Python<'_>,
+    polygon: Bound<'_, PyAny>,
+    inner: Option<bool>,
+    mode: Option<&str>,
+    min_overlap: Option<f64>,
+    max_cells: Option<usize>,
+    target_cell_m: Option<f64>,
+) -> PyResult<HashSet<String>> {
+    check_precision_targets(max_cells, target_cell_m)?;
+    let polygons = extract_polygons(&polygon)?;
+    let mode = parse_coverage_mode(inner, mode, min_overlap)?;
+    let precision = choose_precision(&polygons, max_cells, target_cell_m)?;
+    with_signal_checks(py, |interrupted| {
+        polygons_to_geohashes_bounded(polygons, precision, mode, max_cells, interrupted)
+    })
+}
+
+// ── Circle → geohash ─────────────────────────────────────────────────────────
+
+fn check_radius(radius_m: f64) -> PyResult<()> {
+    if !radius_m.is_finite() || radius_m < 0.0 {
+        return Err(
+            Error::InvalidArgument("radius_m must be a finite non-negative number".into()).into(),
+        );
+    }
+    Ok(())
+}
+
+/// Geohashes within `radius_m` metres (geodesic) of the point (lng, lat).
+///
+/// With `inner=True` only cells lying entirely within the radius are returned,
+/// otherwise every cell the circle touches. `max_cells` and Ctrl-C work as in
+/// `polygon_to_geohashes`.
+#[pyfunction]
+#[pyo3(
+    name = "circle_to_geohashes",
+    signature = (lng, lat, radius_m, precision, inner, *, max_cells=None)
+)]
+fn circle_to_geohashes_py(
+    py: Python<'_>,
+    lng: f64,
+    lat: f64,
+    radius_m: f64,
+    precision: usize,
+    inner: bool,
+    max_cells: Option<usize>,
+) -> PyResult<HashSet<String>> {
+    check_radius(radius_m)?;
+    let center = Point::new(lng, lat);
+    with_signal_checks(py, |interrupted| {
+        circle_to_geohashes_bounded(center, radius_m, precision, inner, max_cells, interrupted)
+    })
+}
+
+/// Geohash sets for many circles given as parallel lists of centres and radii (parallel).
+///
+/// Output order matches input order — `result[i]` covers the circle around
+/// `(lngs[i], lats[i])` with radius `radii_m[i]`.
+#[pyfunction]
+#[pyo3(
+    name = "circles_to_geohashes",
+    signature = (lngs, lats, radii_m, precision, inner, num_threads=None)
+)]
+fn circles_to_geohashes_py(
+    py: Python<'_>,
+    lngs: Vec<f64>,
+    lats: Vec<f64>,
+    radii_m: Vec<f64>,
+    precision: usize,
+    inner: bool,
+    num_threads: Option<usize>,
+) -> PyResult<Vec<HashSet<String>>> {
+    if lngs.len() != lats.len() || lngs.len() != radii_m.len() {
+        return Err(Error::InvalidArgument(
+            "lngs, lats and radii_m must have the same length".into(),
+        )
+        .into());
+    }
+    radii_m.iter().try_for_each(|&r| check_radius(r))?;
+    let circles = lngs
+        .into_iter()
+        .zip(lats)
+        .map(|(lng, lat)| Point::new(lng, lat))
+        .zip(radii_m)
+        .collect();
+    let pool = make_pool(num_threads)?;
+    let raw = py.allow_threads(|| circles_to_geohashes(circles, precision, inner, &pool));
+    raw.into_iter().map(|r| r.map_err(PyErr::from)).collect()
+}
+
+// ── Compaction ───────────────────────────────────────────────────────────────
+
+/// Collect any Python iterable of strings (set, list, tuple, …) into a set.
+fn extract_geohash_set(geohashes: &Bound<'_, PyAny>) -> PyResult<HashSet<String>> {
+    geohashes
+        .try_iter()?
+        .map(|item| item?.extract::<String>())
+        .collect()
+}
+
+/// Merge complete groups of 32 sibling geohashes into their parent (parallel).
+///
+/// Accepts any iterable of geohashes, possibly of mixed precision, and returns the
+/// smallest set of cells covering the same area.
+#[pyfunction]
+#[pyo3(signature = (geohashes, num_threads=None))]
+fn compact_geohashes(
+    py: Python<'_>,
+    geohashes: Bound<'_, PyAny>,
+    num_threads: Option<usize>,
+) -> PyResult<HashSet<String>> {
+    let hash_set = extract_geohash_set(&geohashes)?;
+    let pool = make_pool(num_threads)?;
+    py.allow_threads(|| run_with_pool(&pool, || compact_geohash_set(&hash_set)))
+        .map_err(PyErr::from)
+}
+
+/// Expand every geohash to all of its descendants at `precision` (parallel).
+///
+/// The inverse of `compact_geohashes`. Raises `ValueError` if a hash is finer
+/// than `precision`, and `TooManyCellsError` before building anything past
+/// `max_cells` descendants (a million by default; `None` for no limit).
+#[pyfunction]
+#[pyo3(signature = (geohashes, precision, num_threads=None, *, max_cells=Some(1_000_000)))]
+fn uncompact_geohashes(
+    py: Python<'_>,
+    geohashes: Bound<'_, PyAny>,
+    precision: usize,
+    num_threads: Option<usize>,
+    max_cells: Option<usize>,
+) -> PyResult<HashSet<String>> {
+    let hash_set = extract_geohash_set(&geohashes)?;
+    let pool = make_pool(num_threads)?;
+    py.allow_threads(|| {
+        run_with_pool(&pool, || {
+            uncompact_geohash_set(&hash_set, precision, max_cells)
+        })
+    })
+    .map_err(PyErr::from)
+}
+
+// ── Set algebra ───────────────────────────────────────────────────────────────
+
+/// Union of the areas covered by two iterables of geohashes of any precisions.
+#[pyfunction]
+#[pyo3(name = "geohash_union", signature = (a, b, num_threads=None))]
+fn geohash_union_py(
+    py: Python<'_>,
+    a: Bound<'_, PyAny>,
+    b: Bound<'_, PyAny>,
+    num_threads: Option<usize>,
+) -> PyResult<HashSet<String>> {
+    let (a, b) = (extract_geohash_set(&a)?, extract_geohash_set(&b)?);
+    let pool = make_pool(num_threads)?;
+    py.allow_threads(|| run_with_pool(&pool, || geohash_union(&a, &b)))
+        .map_err(PyErr::from)
+}
+
+/// Intersection of the areas covered by two iterables of geohashes.
+///
+/// A cell intersects its own descendants: `{"f2h"}` and `{"f2h3z"}` give
+/// `{"f2h3z"}`.
+#[pyfunction]
+#[pyo3(name = "geohash_intersection", signature = (a, b, num_threads=None))]
+fn geohash_intersection_py(
+    py: Python<'_>,
+    a: Bound<'_, PyAny>,
+    b: Bound<'_, PyAny>,
+    num_threads: Option<usize>,
+) -> PyResult<HashSet<String>> {
+    let (a, b) = (extract_geohash_set(&a)?, extract_geohash_set(&b)?);
+    let pool = make_pool(num_threads)?;
+    py.allow_threads(|| run_with_pool(&pool, || geohash_intersection(&a, &b)))
+        .map_err(PyErr::from)
+}
+
+/// The area covered by `a` but not by `b`; cells of `a` partly covered by `b`
+/// are split into finer cells.
+#[pyfunction]
+#[pyo3(name = "geohash_difference", signature = (a, b, num_threads=None))]
+fn geohash_difference_py(
+    py: Python<'_>,
+    a: Bound<'_, PyAny>,
+    b: Bound<'_, PyAny>,
+    num_threads: Option<usize>,
+) -> PyResult<HashSet<String>> {
+    let (a, b) = (extract_geohash_set(&a)?, extract_geohash_set(&b)?);
+    let pool = make_pool(num_threads)?;
+    py.allow_threads(|| run_with_pool(&pool, || geohash_difference(&a, &b)))
+        .map_err(PyErr::from)
+}
+
+/// Whether the area covered by `a` includes all of `b`.
+#[pyfunction]
+#[pyo3(name = "geohash_contains", signature = (a, b, num_threads=None))]
+fn geohash_contains_py(
+    py: Python<'_>,
+    a: Bound<'_, PyAny>,
+    b: Bound<'_, PyAny>,
+    num_threads: Option<usize>,
+) -> PyResult<bool> {
+    let (a, b) = (extract_geohash_set(&a)?, extract_geohash_set(&b)?);
+    let pool = make_pool(num_threads)?;
+    py.allow_threads(|| run_with_pool(&pool, || geohash_contains(&a, &b)))
+        .map_err(PyErr::from)
+}
+
+/// Apply a set operation to every pair `(lefts[i], rights[i])` in parallel.
+fn pairwise<T, F>(
+    py: Python<'_>,
+    lefts: Vec<Bound<'_, PyAny>>,
+    rights: Vec<Bound<'_, PyAny>>,
+    num_threads: Option<usize>,
+    op: F,
+) -> PyResult<Vec<T>>
+where
+    T: Send,
+    F: Fn(&HashSet<String>, &HashSet<String>) -> Result<T, Error> + Sync,
+{
+    if lefts.len() != rights.len() {
+        return Err(
+            Error::InvalidArgument("lefts and rights must have the same length".into()).into(),
+        );
+    }
+    let lefts = lefts
+        .iter()
+        .map(extract_geohash_set)
+        .collect::<PyResult<Vec<_>>>()?;
+    let rights = rights
+        .iter()
+        .map(extract_geohash_set)
+        .collect::<PyResult<Vec<_>>>()?;
+    let pool = make_pool(num_threads)?;
+    let raw: Vec<Result<T, Error>> = py.allow_threads(|| {
+        run_with_pool(&pool, || {
+            lefts
+                .par_iter()
+                .zip_eq(&rights)
+                .map(|(a, b)| op(a, b))
+                .collect()
+        })
+    });
+    raw.into_iter().map(|r| r.map_err(PyErr::from)).collect()
+}
+
+/// `geohash_union` of every pair `(lefts[i], rights[i])` (parallel).
+#[pyfunction]
+#[pyo3(signature = (lefts, rights, num_threads=None))]
+fn geohash_union_many(
+    py: Python<'_>,
+    lefts: Vec<Bound<'_, PyAny>>,
+    rights: Vec<Bound<'_, PyAny>>,
+    num_threads: Option<usize>,
+) -> PyResult<Vec<HashSet<String>>> {
+    pairwise(py, lefts, rights, num_threads, geohash_union)
+}
+
+/// `geohash_intersection` of every pair `(lefts[i], rights[i])` (parallel).
+#[pyfunction]
+#[pyo3(signature = (lefts, rights, num_threads=None))]
+fn geohash_intersection_many(
+    py: Python<'_>,
+    lefts: Vec<Bound<'_, PyAny>>,
+    rights: Vec<Bound<'_, PyAny>>,
+    num_threads: Option<usize>,
+) -> PyResult<Vec<HashSet<String>>> {
+    pairwise(py, lefts, rights, num_threads, geohash_intersection)
+}
+
+/// `geohash_difference` of every pair `(lefts[i], rights[i])` (parallel).
+#[pyfunction]
+#[pyo3(signature = (lefts, rights, num_threads=None))]
+fn geohash_difference_many(
+    py: Python<'_>,
+    lefts: Vec<Bound<'_, PyAny>>,
+    rights: Vec<Bound<'_, PyAny>>,
+    num_threads: Option<usize>,
+) -> PyResult<Vec<HashSet<String>>> {
+    pairwise(py, lefts, rights, num_threads, geohash_difference)
+}
+
+/// `geohash_contains` of every pair `(lefts[i], rights[i])` (parallel).
+#[pyfunction]
+#[pyo3(signature = (lefts, rights, num_threads=None))]
+fn geohash_contains_many(
+    py: Python<'_>,
+    lefts: Vec<Bound<'_, PyAny>>,
+    rights: Vec<Bound<'_, PyAny>>,
+    num_threads: Option<usize>,
+) -> PyResult<Vec<bool>> {
+    pairwise(py, lefts, rights, num_threads, geohash_contains)
+}
+
+// ── Cell hierarchy ────────────────────────────────────────────────────────────
+
+/// Default to one level coarser (`parent`) or finer (`children`) than `hash`.
+fn relative_precision(hash: &str, precision: Option<usize>, step: isize) -> usize {
+    precision.unwrap_or_else(|| hash.len().saturating_add_signed(step))
+}
+
+/// The up to 8 cells around a geohash, from south-west to north-east.
+///
+/// Cells wrap across the antimeridian; a cell touching a pole has only 5.
+#[pyfunction]
+#[pyo3(name = "neighbors")]
+fn neighbors_py(hash: &str) -> PyResult<Vec<String>> {
+    Ok(all_neighbors(hash)?)
+}
+
+/// `neighbors` of every geohash in a list (parallel).
+#[pyfunction]
+#[pyo3(signature = (geohashes, num_threads=None))]
+fn neighbors_many(
+    py: Python<'_>,
+    geohashes: Vec<String>,
+    num_threads: Option<usize>,
+) -> PyResult<Vec<Vec<String>>> {
+    let pool = make_pool(num_threads)?;
+    let raw: Vec<Result<Vec<String>, Error>> = py.allow_threads(|| {
+        run_with_pool(&pool, || {
+            geohashes.par_iter().map(|h| all_neighbors(h)).collect()
+        })
+    });
+    raw.into_iter().map(|r| r.map_err(PyErr::from)).collect()
+}
+
+/// The ancestor of a geohash at `precision`, one level up by default.
+#[pyfunction]
+#[pyo3(signature = (hash, precision=None))]
+fn parent(hash: &str, precision: Option<usize>) -> PyResult<String> {
+    Ok(geohash_parent(
+        hash,
+        relative_precision(hash, precision, -1),
+    )?)
+}
+
+/// `parent` of every geohash in a list (parallel).
+#[pyfunction]
+#[pyo3(signature = (geohashes, precision=None, num_threads=None))]
+fn parent_many(
+    py: Python<'_>,
+    geohashes: Vec<String>,
+    precision: Option<usize>,
+    num_threads: Option<usize>,
+) -> PyResult<Vec<String>> {
+    let pool = make_pool(num_threads)?;
+    let raw: Vec<Result<String, Error>> = py.allow_threads(|| {
+        run_with_pool(&pool, || {
+            geohashes
+                .par_iter()
+                .map(|h| geohash_parent(h, relative_precision(h, precision, -1)))
+                .collect()
+        })
+    });
+    raw.into_iter().map(|r| r.map_err(PyErr::from)).collect()
+}
+
+/// `geohash_children`, collected, failing with `Error::TooManyCells` once
+/// there are more than `max_cells` of them.
+fn collect_children(
+    hash: &str,
+    precision: Option<usize>,
+    max_cells: Option<usize>,
+) -> Result<Vec<String>, Error> {
+    let take = max_cells.map_or(usize::MAX, |limit| limit.saturating_add(1));
+    let children: Vec<String> = geohash_children(hash, relative_precision(hash, precision, 1))?
+        .take(take)
+        .collect();
+    check_max_cells(children.len(), max_cells)?;
+    Ok(children)
+}
+
+/// The descendants of a geohash at `precision`, in geohash order; by default
+/// its 32 children. Raises `TooManyCellsError` past `max_cells` descendants
+/// (a million by default; `None` for no limit).
+#[pyfunction]
+#[pyo3(signature = (hash, precision=None, *, max_cells=Some(1_000_000)))]
+fn children(
+    hash: &str,
+    precision: Option<usize>,
+    max_cells: Option<usize>,
+) -> PyResult<Vec<String>> {
+    Ok(collect_children(hash, precision, max_cells)?)
+}
+
+/// `children` of every geohash in a list (parallel); `max_cells` applies to
+/// each geohash.
+#[pyfunction]
+#[pyo3(signature = (geohashes, precision=None, num_threads=None, *, max_cells=Some(1_000_000)))]
+fn children_many(
+    py: Python<'_>,
+    geohashes: Vec<String>,
+    precision: Option<usize>,
+    num_threads: Option<usize>,
+    max_cells: Option<usize>,
+) -> PyResult<Vec<Vec<String>>> {
+    let pool = make_pool(num_threads)?;
+    let raw: Vec<Result<Vec<String>, Error>> = py.allow_threads(|| {
+        run_with_pool(&pool, || {
+            geohashes
+                .par_iter()
+                .map(|h| collect_children(h, precision, max_cells))
+                .collect()
+        })
+    });
+    raw.into_iter().map(|r| r.map_err(PyErr::from)).collect()
+}
+
+// ── k-rings ───────────────────────────────────────────────────────────────────
+
+/// Every cell within `k` steps (neighbour moves) of a geohash, itself included.
+/// Raises `TooManyCellsError` past `max_cells` cells (a million by default;
+/// `None` for no limit).
+#[pyfunction]
+#[pyo3(signature = (hash, k, *, max_cells=Some(1_000_000)))]
+fn k_ring(hash: &str, k: usize, max_cells: Option<usize>) -> PyResult<Vec<String>> {
+    Ok(geohash_k_ring(hash, k, max_cells)?)
+}
+
+/// The hollow square of cells exactly `k` steps from a geohash. Raises
+/// `TooManyCellsError` past `max_cells` cells, as `k_ring`.
+#[pyfunction]
+#[pyo3(signature = (hash, k, *, max_cells=Some(1_000_000)))]
+fn ring(hash: &str, k: usize, max_cells: Option<usize>) -> PyResult<Vec<String>> {
+    Ok(geohash_ring(hash, k, max_cells)?)
+}
+
+/// `geohash_k_ring` or `geohash_ring`.
+type RingFn = fn(&str, usize, Option<usize>) -> Result<Vec<String>, Error>;
+
+/// Apply `f` with `k` and `max_cells` to every geohash in a list (parallel).
+fn map_rings(
+    py: Python<'_>,
+    geohashes: Vec<String>,
+    k: usize,
+    num_threads: Option<usize>,
+    max_cells: Option<usize>,
+    f: RingFn,
+) -> PyResult<Vec<Vec<String>>> {
+    let pool = make_pool(num_threads)?;
+    let raw: Vec<Result<Vec<String>, Error>> = py.allow_threads(|| {
+        run_with_pool(&pool, || {
+            geohashes.par_iter().map(|h| f(h, k, max_cells)).collect()
+        })
+    });
+    raw.into_iter().map(|r| r.map_err(PyErr::from)).collect()
+}
+
+/// `k_ring` of every geohash in a list (parallel); `max_cells` applies to
+/// each geohash.
+#[pyfunction]
+#[pyo3(signature = (geohashes, k, num_threads=None, *, max_cells=Some(1_000_000)))]
+fn k_ring_many(
+    py: Python<'_>,
+    geohashes: Vec<String>,
+    k: usize,
+    num_threads: Option<usize>,
+    max_cells: Option<usize>,
+) -> PyResult<Vec<Vec<String>>> {
+    map_rings(py, geohashes, k, num_threads, max_cells, geohash_k_ring)
+}
+
+/// `ring` of every geohash in a list (parallel); `max_cells` applies to each
+/// geohash.
+#[pyfunction]
+#[pyo3(signature = (geohashes, k, num_threads=None, *, max_cells=Some(1_000_000)))]
+fn ring_many(
+    py: Python<'_>,
+    geohashes: Vec<String>,
+    k: usize,
+    num_threads: Option<usize>,
+    max_cells: Option<usize>,
+) -> PyResult<Vec<Vec<String>>> {
+    map_rings(py, geohashes, k, num_threads, max_cells, geohash_ring)
+}
+
+// ── NumPy arrays ──────────────────────────────────────────────────────────────
+
+// NumPy is not a dependency: arrays are read and written through the buffer
+// protocol, and the numpy module is only imported to allocate results for
+// callers that passed arrays in.
+
+/// A column of floats, flagged when it came from a NumPy array. A contiguous
+/// float64 buffer (a NumPy array, `array.array`, `memoryview`, ...) is read in
+/// place; strided buffers, other dtypes and plain sequences are copied.
+enum FloatColumn {
+    Buffer(PyBuffer<f64>, bool),
+    Owned(Vec<f64>, bool),
+}
+
+impl<'py> FromPyObject<'py> for FloatColumn {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        let is_array = is_ndarray(ob);
+        match PyBuffer::<f64>::get(ob) {
+            Ok(buffer) if buffer.dimensions() == 1 && buffer.is_c_contiguous() => {
+                Ok(FloatColumn::Buffer(buffer, is_array))
+            }
+            Ok(buffer) if buffer.dimensions() == 1 => {
+                Ok(FloatColumn::Owned(buffer.to_vec(ob.py())?, is_array))
+            }
+            _ => Ok(FloatColumn::Owned(ob.extract()?, is_array)),
+        }
+    }
+}
+
+impl FloatColumn {
+    /// The values. A buffer is read in place, so the GIL must be held while the
+    /// slice is in use (see `allow_threads_unless`).
+    fn as_slice(&self) -> &[f64] {
+        match self {
+            // SAFETY: the buffer is contiguous, holds `item_count` float64s and
+            // stays alive while `self` holds it; with the GIL held, no Python
+            // code can write to it meanwhile.
+            FloatColumn::Buffer(buffer, _) => unsafe {
+                std::slice::from_raw_parts(buffer.buf_ptr() as *const f64, buffer.item_count())
+            },
+            FloatColumn::Owned(values, _) => values,
+        }
+    }
+
+    fn is_array(&self) -> bool {
+        match self {
+            FloatColumn::Buffer(_, is_array) | FloatColumn::Owned(_, is_array) => *is_array,
+        }
+    }
+
+    fn in_place(&self) -> bool {
+        matches!(self, FloatColumn::Buffer(..))
+    }
+}
+
+/// Run `f` with the GIL released, unless it reads a Python buffer in place.
+///
+/// Holding the GIL keeps Python code from writing to the buffer while `f`, and
+/// the Rayon threads it starts, read it; those threads never need the GIL.
+fn allow_threads_unless<T, F>(py: Python<'_>, in_place: bool, f: F) -> T
+where
+    F: pyo3::marker::Ungil + FnOnce() -> T,
+    T: pyo3::marker::Ungil,
+{
+    if in_place {
+        f()
+    } else {
+        py.allow_threads(f)
+    }
+}
+
+fn is_ndarray(ob: &Bound<'_, PyAny>) -> bool {
+    ob.hasattr("__array_interface__").unwrap_or(false)
+}
+
+/// A new NumPy array of `shape` and `dtype`, with a writable view of its data.
+fn new_array<'py, T: pyo3::buffer::Element>(
+    py: Python<'py>,
+    shape: impl IntoPyObject<'py>,
+    dtype: &str,
+) -> PyResult<(Bound<'py, PyAny>, PyBuffer<T>)> {
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("dtype", dtype)?;
+    let array = py
+        .import("numpy")?
+        .call_method("empty", (shape,), Some(&kwargs))?;
+    let buffer = PyBuffer::<T>::get(&array)?;
+    Ok((array, buffer))
+}
+
+/// Mutable view of a buffer freshly allocated by `new_array`.
+///
+/// # Safety
+/// Nothing else may reference the array's data while the slice is alive.
+unsafe fn fresh_slice_mut<T: pyo3::buffer::Element>(buffer: &mut PyBuffer<T>) -> &mut [T] {
+    std::slice::from_raw_parts_mut(buffer.buf_ptr() as *mut T, buffer.item_count())
+}
+
+/// Encode into a NumPy array of `S<precision>` bytes.
+fn encode_to_array(
+    py: Python<'_>,
+    lngs: &[f64],
+    lats: &[f64],
+    precision: usize,
+    pool: &Option<rayon::ThreadPool>,
+    in_place: bool,
+) -> PyResult<PyObject> {
+    if precision == 0 || precision > 12 {
+        return Err(Error::InvalidPrecision(precision).into());
+    }
+    let (array, mut buffer) = new_array::<u8>(py, lngs.len() * precision, "uint8")?;
+    // SAFETY: `array` was just created and is not shared yet.
+    let bytes = unsafe { fresh_slice_mut(&mut buffer) };
+    allow_threads_unless(py, in_place, || {
+        run_with_pool(pool, || {
+            bytes
+                .par_chunks_mut(precision)
+                .zip_eq(lngs.par_iter().zip_eq(lats))
+                .try_for_each(|(out, (&lng, &lat))| {
+                    let hash = encode((lng, lat).into(), precision)?;
+                    out.copy_from_slice(hash.as_bytes());
+                    Ok::<_, Error>(())
+                })
+        })
+    })?;
+    Ok(array
+        .call_method1("view", (format!("S{precision}"),))?
+        .unbind())
+}
+
+/// Decode a NumPy array of geohashes into an `(n, 2)` array of centres.
+fn decode_array(
+    py: Python<'_>,
+    geohashes: &Bound<'_, PyAny>,
+    pool: &Option<rayon::ThreadPool>,
+) -> PyResult<PyObject> {
+    let dtype = geohashes.getattr("dtype")?;
+    let hashes = if dtype.getattr("kind")?.extract::<String>()? == "S" {
+        // fixed-width bytes, NUL-padded: read them in place as uint8, once made
+        // contiguous (which only copies a strided array)
+        let width: usize = dtype.getattr("itemsize")?.extract()?;
+        let contiguous = py
+            .import("numpy")?
+            .call_method1("ascontiguousarray", (geohashes,))?;
+        let buffer = PyBuffer::<u8>::get(&contiguous.call_method1("view", ("uint8",))?)?;
+        HashColumn::Bytes(buffer, width)
+    } else {
+        HashColumn::Strings(geohashes.call_method0("tolist")?.extract()?)
+    };
+    let n = hashes.len();
+    let (array, mut buffer) = new_array::<f64>(py, (n, 2), "float64")?;
+    // SAFETY: `array` was just created and is not shared yet.
+    let centers = unsafe { fresh_slice_mut(&mut buffer) };
+    let in_place = matches!(hashes, HashColumn::Bytes(..));
+    allow_threads_unless(py, in_place, || {
+        run_with_pool(pool, || {
+            centers
+                .par_chunks_mut(2)
+                .enumerate()
+                .try_for_each(|(i, out)| {
+                    let center = decode_bbox(hashes.get(i)?)?.center();
+                    out.copy_from_slice(&[center.x, center.y]);
+                    Ok::<_, Error>(())
+                })
+        })
+    })?;
+    Ok(array.unbind())
+}
+
+/// The geohashes of a NumPy array: fixed-width bytes read in place, or strings.
+enum HashColumn {
+    Bytes(PyBuffer<u8>, usize),
+    Strings(Vec<String>),
+}
+
+impl HashColumn {
+    fn len(&self) -> usize {
+        match self {
+            HashColumn::Bytes(buffer, width) => buffer.item_count() / width.max(&1),
+            HashColumn::Strings(hashes) => hashes.len(),
+        }
+    }
+
+    /// The `i`th geohash. Bytes are read in place, so the GIL must be held, as
+    /// for `FloatColumn::as_slice`.
+    fn get(&self, i: usize) -> Result<&str, Error> {
+        match self {
+            HashColumn::Bytes(buffer, width) => {
+                // SAFETY: as in `FloatColumn::as_slice`.
+                let bytes = unsafe {
+                    std::slice::from_raw_parts(buffer.buf_ptr() as *const u8, buffer.item_count())
+                };
+                let item = &bytes[i * width..(i + 1) * width];
+                let end = item.iter().position(|&b| b == 0).unwrap_or(*width);
+                std::str::from_utf8(&item[..end]).map_err(|_| {
+                    Error::InvalidHash(format!("{:?} is not valid UTF-8", &item[..end]))
+                })
+            }
+            HashColumn::Strings(hashes) => Ok(&hashes[i]),
+        }
+    }
+}
+
+// ── Encode / decode ───────────────────────────────────────────────────────────
+
+/// Encode a single (lng, lat) coordinate to a geohash of the given precision.
+#[pyfunction]
+#[pyo3(name = "encode")]
+fn encode_py(lng: f64, lat: f64, precision: usize) -> PyResult<String> {
+    Ok(encode((lng, lat).into(), precision).map_err(Error::from)?)
+}
+
+/// Encode parallel lists of longitudes and latitudes to geohashes (parallel).
+///
+/// `lngs` and `lats` may be NumPy arrays or other float64 buffers, which are
+/// read in place when contiguous. If either is a NumPy array, the result is a NumPy array of fixed-width bytes
+/// (`S<precision>`) rather than a list of strings.
+#[pyfunction]
+#[pyo3(signature = (lngs, lats, precision, num_threads=None))]
+fn encode_many(
+    py: Python<'_>,
+    lngs: FloatColumn,
+    lats: FloatColumn,
+    precision: usize,
+    num_threads: Option<usize>,
+) -> PyResult<PyObject> {
+    let as_array = lngs.is_array() || lats.is_array();
+    let in_place = lngs.in_place() || lats.in_place();
+    let (lngs, lats) = (lngs.as_slice(), lats.as_slice());
+    if lngs.len() != lats.len() {
+        return Err(
+            Error::InvalidArgument("lngs and lats must have the same length".into()).into(),
+        );
+    }
+    let pool = make_pool(num_threads)?;
+    if as_array {
+        return encode_to_array(py, lngs, lats, precision, &pool, in_place);
+    }
+    let raw: Vec<Result<String, geohash::GeohashError>> =
+        allow_threads_unless(py, in_place, || {
+            run_with_pool(&pool, || {
+                lngs.par_iter()
+                    .zip_eq(lats)
+                    .map(|(&lng, &lat)| encode((lng, lat).into(), precision))
+                    .collect()
+            })
+        });
+    let hashes = raw
+        .into_iter()
+        .map(|r| r.map_err(Error::from))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(hashes.into_pyobject(py)?.into_any().unbind())
+}
+
+/// Decode a geohash to (lng, lat, lng_err, lat_err) — lng-first, matching encode convention.
+#[pyfunction]
+fn decode_exactly(hash_str: &str) -> PyResult<(f64, f64, f64, f64)> {
+    let bbox = decode_bbox(hash_str).map_err(Error::from)?;
+    let lat = (bbox.min().y + bbox.max().y) / 2.0;
+    let lng = (bbox.min().x + bbox.max().x) / 2.0;
+    let lat_err = (bbox.max().y - bbox.min().y) / 2.0;
+    let lng_err = (bbox.max().x - bbox.min().x) / 2.0;
+    Ok((lng, lat, lng_err, lat_err))
+}
+
+/// Decode a list of geohashes to (lng, lat) center pairs (parallel).
+///
+/// `geohashes` may also be a NumPy array of bytes (`S<precision>`, as returned
+/// by `encode_many`) or strings, in which case the result is an `(n, 2)` float64
+/// array of longitudes and latitudes.
+#[pyfunction]
+#[pyo3(signature = (geohashes, num_threads=None))]
+fn decode_many(
+    py: Python<'_>,
+    geohashes: Bound<'_, PyAny>,
+    num_threads: Option<usize>,
+) -> PyResult<PyObject> {
+    let pool = make_pool(num_threads)?;
+    if is_ndarray(&geohashes) {
+        return decode_array(py, &geohashes, &pool);
+    }
+    let geohashes: Vec<String> = geohashes.extract()?;
+    let raw: Vec<Result<(f64, f64), geohash::GeohashError>> = py.allow_threads(|| {
+        run_with_pool(&pool, || {
+            geohashes
+                .into_par_iter()
+                .map(|hash| decode_bbox(&hash).map(|bbox| bbox.center().x_y()))
+                .collect()
+        })
+    });
+    let centers = raw
+        .into_iter()
+        .map(|r| r.map_err(Error::from))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(centers.into_pyobject(py)?.into_any().unbind())
+}
+
+/// Decode a list of geohashes to (lng, lat, lng_err, lat_err) tuples (parallel).
+#[pyfunction]
+#[pyo3(signature = (geohashes, num_threads=None))]
+fn decode_many_exactly(
+    py: Python<'_>,
+    geohashes: Vec<String>,
+    num_threads: Option<usize>,
+) -> PyResult<Vec<(f64, f64, f64, f64)>> {
+    let pool = make_pool(num_threads)?;
+    let raw: Vec<Result<(f64, f64, f64, f64), geohash::GeohashError>> = py.allow_threads(|| {
+        run_with_pool(&pool, || {
+            geohashes
+                .into_par_iter()
+                .map(|hash| {
+                    decode_bbox(&hash).map(|bbox| {
+                        let lat = (bbox.min().y + bbox.max().y) / 2.0;
+                        let lng = (bbox.min().x + bbox.max().x) / 2.0;
+                        let lat_err = (bbox.max().y - bbox.min().y) / 2.0;
+                        let lng_err = (bbox.max().x - bbox.min().x) / 2.0;
+                        (lng, lat, lng_err, lat_err)
+                    })
+                })
+                .collect()
+        })
+    });
+    raw.into_iter()
+        .map(|r| r.map_err(|e| Error::from(e).into()))
+        .collect()
+}
+
+// ── WKB / EWKB output ────────────────────────────────────────────────────────
+
+fn into_py_wkb_results(raw: Vec<Result<Vec<u8>, Error>>) -> PyResult<Vec<Vec<u8>>> {
+    raw.into_iter().map(|r| r.map_err(PyErr::from)).collect()
+}
+
+/// Decode a list of geohashes to WKB polygon bytes representing their bounding boxes (parallel).
+///
+/// Each returned bytes value is a standard little-endian WKB polygon with one ring of five
+/// points (closed bounding box). Pass the result to `ST_GeomFromWKB` in DuckDB or PostGIS.
+#[pyfunction]
+#[pyo3(signature = (geohashes, num_threads=None))]
+fn decode_many_to_wkb(
+    py: Python<'_>,
+    geohashes: Vec<String>,
+    num_threads: Option<usize>,
+) -> PyResult<Vec<Vec<u8>>> {
+    let pool = make_pool(num_threads)?;
+    into_py_wkb_results(py.allow_threads(|| geohashes_to_wkb(geohashes, &pool)))
+}
+
+/// Decode a list of geohashes to EWKB polygon bytes with an embedded SRID (parallel).
+///
+/// Like `decode_many_to_wkb` but with a SRID embedded in the header, making the
+/// bytes suitable for direct insertion into PostGIS geometry columns without a
+/// separate `ST_SetSRID` call. `srid` defaults to 4326.
+#[pyfunction]
+#[pyo3(signature = (geohashes, srid=4326, num_threads=None))]
+fn decode_many_to_ewkb(
+    py: Python<'_>,
+    geohashes: Vec<String>,
+    srid: u32,
+    num_threads: Option<usize>,
+) -> PyResult<Vec<Vec<u8>>> {
+    let pool = make_pool(num_threads)?;
+    into_py_wkb_results(py.allow_threads(|| geohashes_to_ewkb(geohashes, srid, &pool)))
+}
+
+// ── Geohash set → dissolved polygon ──────────────────────────────────────────
+
+/// Dissolve geohashes on `pool`, releasing the GIL, and map errors to `ValueError`.
+fn dissolve_py(
+    py: Python<'_>,
+    geohashes: &Bound<'_, PyAny>,
+    num_threads: Option<usize>,
+) -> PyResult<MultiPolygon> {
+    let hash_set = extract_geohash_set(geohashes)?;
+    let pool = make_pool(num_threads)?;
+    py.allow_threads(|| run_with_pool(&pool, || geohashes_to_multipolygon(&hash_set)))
+        .map_err(PyErr::from)
+}
+
+/// Dissolve a geohash set into a MultiPolygon, returned as a `__geo_interface__` mapping.
+///
+/// Adjacent cells are merged and enclosed gaps become holes; mixed precisions are
+/// supported. Pass the result to `shapely.geometry.shape` to get a Shapely geometry.
+#[pyfunction]
+#[pyo3(signature = (geohashes, num_threads=None))]
+fn geohashes_to_polygon<'py>(
+    py: Python<'py>,
+    geohashes: Bound<'py, PyAny>,
+    num_threads: Option<usize>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let mp = dissolve_py(py, &geohashes, num_threads)?;
+    let coordinates: Vec<Vec<Vec<(f64, f64)>>> = mp
+        .iter()
+        .map(|polygon| {
+            std::iter::once(polygon.exterior())
+                .chain(polygon.interiors())
+                .map(|ring| ring.coords().map(|c| (c.x, c.y)).collect())
+                .collect()
+        })
+        .collect();
+    let geo_interface = PyDict::new(py);
+    geo_interface.set_item("type", "MultiPolygon")?;
+    geo_interface.set_item("coordinates", coordinates)?;
+    Ok(geo_interface)
+}
+
+/// Dissolve a geohash set into a MultiPolygon, returned as WKB bytes.
+#[pyfunction]
+#[pyo3(signature = (geohashes, num_threads=None))]
+fn geohashes_to_polygon_wkb(
+    py: Python<'_>,
+    geohashes: Bound<'_, PyAny>,
+    num_threads: Option<usize>,
+) -> PyResult<Vec<u8>> {
+    let mp = dissolve_py(py, &geohashes, num_threads)?;
+    Ok(serialize_multipolygon(&mp, None))
+}
+
+/// Dissolve a geohash set into a MultiPolygon, returned as EWKB bytes with an
+/// embedded SRID (defaults to 4326).
+#[pyfunction]
+#[pyo3(signature = (geohashes, srid=4326, num_threads=None))]
+fn geohashes_to_polygon_ewkb(
+    py: Python<'_>,
+    geohashes: Bound<'_, PyAny>,
+    srid: u32,
+    num_threads: Option<usize>,
+) -> PyResult<Vec<u8>> {
+    let mp = dissolve_py(py, &geohashes, num_threads)?;
+    Ok(serialize_multipolygon(&mp, Some(srid)))
+}
+
+// ── Geography expansion ───────────────────────────────────────────────────────
+
+fn check_distance_m(name: &str, distance_m: f64) -> PyResult<()> {
+    if !distance_m.is_finite() || distance_m < 0.0 {
+        return Err(
+            Error::InvalidArgument(format!("{name} must be a finite non-negative number")).into(),
+        );
+    }
+    Ok(())
+}
+
+/// Reject a group whose hashes don't all share one precision.
+fn check_same_precision(geohashes: &[String]) -> PyResult<()> {
+    if let Some(h) = geohashes.first() {
+        if geohashes.iter().any(|gh| gh.len() != h.len()) {
+            return Err(Error::MixedPrecision.into());
+        }
+    }
+    Ok(())
+}
+
+/// A distance in metres for every group, or a single one shared by all of them.
+#[derive(FromPyObject)]
+enum GroupDistances {
+    Shared(f64),
+    PerGroup(Vec<f64>),
+}
+
+impl GroupDistances {
+    fn get(&self, group: usize) -> f64 {
+        match self {
+            GroupDistances::Shared(d) => *d,
+            GroupDistances::PerGroup(ds) => ds[group],
+        }
+    }
+}
+
+/// Run `f` on every group and its distance in parallel with the GIL released,
+/// in input order. `name` is the distance argument, for error messages.
+fn map_groups<F>(
+    py: Python<'_>,
+    groups: Vec<Vec<String>>,
+    name: &str,
+    distances: GroupDistances,
+    f: F,
+) -> PyResult<Vec<Vec<String>>>
+where
+    F: Fn(&HashSet<String>, f64) -> Result<HashSet<String>, Error> + Sync,
+{
+    if let GroupDistances::PerGroup(ds) = &distances {
+        if ds.len() != groups.len() {
+            return Err(Error::InvalidArgument(format!(
+                "{name} must be a number or a list with one distance per group \
+                 ({} groups, {} distances)",
+                groups.len(),
+                ds.len()
+            ))
+            .into());
+        }
+    }
+    // Validate arguments sequentially (fast, may raise PyErr) before releasing the GIL.
+    for i in 0..groups.len() {
+        check_distance_m(name, distances.get(i))?;
+    }
+    let raw: Vec<Result<Vec<String>, Error>> = py.allow_threads(|| {
+        groups
+            .into_par_iter()
+            .enumerate()
+            .map(|(i, hashes)| {
+                let hash_set: HashSet<String> = hashes.into_iter().collect();
+                f(&hash_set, distances.get(i)).map(|s| s.into_iter().collect())
+            })
+            .collect()
+    });
+    raw.into_iter().map(|r| r.map_err(PyErr::from)).collect()
+}
+
+/// Expand on the hop grid, or to exact spherical distances with `exact`, at
+/// the finest precision of the set; `compact` merges the result back.
+fn expand_set(
+    hash_set: &HashSet<String>,
+    expansion_m: f64,
+    exact: bool,
+    compact: bool,
+) -> Result<HashSet<String>, Error> {
+    let expand: fn(&HashSet<String>, f64) -> Result<HashSet<String>, Error> = if exact {
+        expand_geohash_set_exact
+    } else {
+        expand_geohash_set_by_distance
+    };
+    let expanded = expand_mixed_geohash_set(hash_set, expansion_m, expand)?;
+    if compact {
+        compact_geohash_set(&expanded)
+    } else {
+        Ok(expanded)
+    }
+}
+
+/// Expand a single group of geohashes outward by `expansion_m` metres.
+///
+/// Hop counts follow the cell size at each latitude, so this works for any
+/// precision level and stays bounded near the poles. With `exact=True`, only
+/// cells whose nearest edge is within `expansion_m` of the group, measured on
+/// the sphere, are kept.
+///
+/// A mixed-precision group is expanded at its finest precision: its cells are
+/// returned as they are, with the added cells at the finest precision, or
+/// merged into the smallest covering set with `compact=True`.
+#[pyfunction]
+#[pyo3(signature = (geohashes, expansion_m, exact=false, compact=false))]
+fn expand_geohashes(
+    py: Python<'_>,
+    geohashes: Vec<String>,
+    expansion_m: f64,
+    exact: bool,
+    compact: bool,
+) -> PyResult<Vec<String>> {
+    if geohashes.is_empty() {
+        return Ok(vec![]);
+    }
+    check_distance_m("expansion_m", expansion_m)?;
+    let hash_set: HashSet<String> = geohashes.into_iter().collect();
+    py.allow_threads(|| expand_set(&hash_set, expansion_m, exact, compact))
+        .map(|s| s.into_iter().collect())
+        .map_err(PyErr::from)
+}
+
+/// Labels of the cells added by `expand_geohashes_with_distance`: distances in
+/// metres, band indices or hop indices.
+#[derive(IntoPyObject)]
+enum DistanceLabels {
+    Metres(HashMap<String, f64>),
+    Bands(HashMap<String, usize>),
+    Hops(HashMap<String, usize>),
+}
+
+/// Expand a single group of geohashes by up to `max_m` metres and label every
+/// added cell with how far out it is.
+///
+/// Distances are measured on the sphere from the nearest edge of the group, as
+/// with `exact=True` in `expand_geohashes`. Without `bands`, each cell maps to
+/// its distance in metres; with increasing `bands` such as `[250, 500, 1000]`,
+/// it maps to the index of the first band it falls within, or `len(bands)`
+/// when it lies beyond the last one. With `hops=True`, it maps instead to the
+/// ring of the expansion it was reached in, 1 for the cells touching the group.
+#[pyfunction]
+#[pyo3(signature = (geohashes, max_m, bands=None, *, hops=false))]
+fn expand_geohashes_with_distance(
+    py: Python<'_>,
+    geohashes: Vec<String>,
+    max_m: f64,
+    bands: Option<Vec<f64>>,
+    hops: bool,
+) -> PyResult<DistanceLabels> {
+    check_same_precision(&geohashes)?;
+    check_distance_m("max_m", max_m)?;
+    if hops && bands.is_some() {
+        return Err(Error::InvalidArgument("bands and hops can't be combined".into()).into());
+    }
+    if let Some(bands) = &bands {
+        if bands.iter().any(|b| !b.is_finite() || *b < 0.0)
+            || bands.windows(2).any(|w| w[0] >= w[1])
+        {
+            return Err(Error::InvalidArgument(
+                "bands must be finite non-negative distances in increasing order".into(),
+            )
+            .into());
+        }
+    }
+    let hash_set: HashSet<String> = geohashes.into_iter().collect();
+    let labels = py
+        .allow_threads(|| expand_geohash_set_with_distance(&hash_set, max_m))
+        .map_err(PyErr::from)?;
+    let labels = labels.into_iter();
+    Ok(match bands {
+        None if hops => DistanceLabels::Hops(labels.map(|(h, l)| (h, l.hops)).collect()),
+        None => DistanceLabels::Metres(labels.map(|(h, l)| (h, l.distance_m)).collect()),
+        Some(bands) => DistanceLabels::Bands(
+            labels
+                .map(|(h, l)| (h, bands.partition_point(|&b| b < l.distance_m)))
+                .collect(),
+        ),
+    })
+}
+
+/// Expand multiple groups of geohashes outward by `expansion_m` metres.
+///
+/// Each input group is expanded independently. Output order matches input order —
+/// `result[i]` is the expanded version of `groups[i]`. Groups are processed in
+/// parallel across geographies via Rayon.
+///
+/// Hop counts follow each group's own cell size, so groups at different
+/// precision levels are each handled correctly. `expansion_m` is either one
+/// distance for every group or a list with one per group. `exact` and
+/// `compact` are as in `expand_geohashes`.
+#[pyfunction]
+#[pyo3(signature = (groups, expansion_m, exact=false, compact=false))]
+fn expand_geohash_mapping(
+    py: Python<'_>,
+    groups: Vec<Vec<String>>,
+    expansion_m: GroupDistances,
+    exact: bool,
+    compact: bool,
+) -> PyResult<Vec<Vec<String>>> {
+    map_groups(py, groups, "expansion_m", expansion_m, |hash_set, d| {
+        expand_set(hash_set, d, exact, compact)
+    })
+}
+
+/// Erode on the hop grid, or to exact spherical distances with `exact`.
+fn erode_set(
+    hash_set: &HashSet<String>,
+    erosion_m: f64,
+    exact: bool,
+) -> Result<HashSet<String>, Error> {
+    if exact {
+        erode_geohash_set_exact(hash_set, erosion_m)
+    } else {
+        erode_geohash_set_by_distance(hash_set, erosion_m)
+    }
+}
+
+/// Shrink a single group of geohashes inward by `erosion_m` metres.
+///
+/// The inverse of `expand_geohashes`: every cell within `erosion_m` of a cell
+/// outside the group is dropped, leaving its core. `exact` is as in
+/// `expand_geohashes`.
+#[pyfunction]
+#[pyo3(signature = (geohashes, erosion_m, exact=false))]
+fn erode_geohashes(
+    py: Python<'_>,
+    geohashes: Vec<String>,
+    erosion_m: f64,
+    exact: bool,
+) -> PyResult<Vec<String>> {
+    check_same_precision(&geohashes)?;
+    check_distance_m("erosion_m", erosion_m)?;
+    let hash_set: HashSet<String> = geohashes.into_iter().collect();
+    py.allow_threads(|| erode_set(&hash_set, erosion_m, exact))
+        .map(|s| s.into_iter().collect())
+        .map_err(PyErr::from)
+}
+
+/// Shrink multiple groups of geohashes inward by `erosion_m` metres.
+///
+/// `result[i]` is the eroded version of `groups[i]`; groups are processed in
+/// parallel via Rayon and `erosion_m` may be given per group, as in
+/// `expand_geohash_mapping`.
+#[pyfunction]
+#[pyo3(signature = (groups, erosion_m, exact=false))]
+fn erode_geohash_mapping(
+    py: Python<'_>,
+    groups: Vec<Vec<String>>,
+    erosion_m: GroupDistances,
+    exact: bool,
+) -> PyResult<Vec<Vec<String>>> {
+    groups.iter().try_for_each(|g| check_same_precision(g))?;
+    map_groups(py, groups, "erosion_m", erosion_m, |hash_set, d| {
+        erode_set(hash_set, d, exact)
+    })
+}
+
+// ── Packed integer geohashes ──────────────────────────────────────────────────
+
+/// Unpack integer geohashes into their strings.
+fn unpack(values: impl IntoIterator<Item = u64>) -> Result<Vec<String>, Error> {
+    values
+        .into_iter()
+        .map(|v| PackedGeohash::try_from(v).map(|h| h.to_string()))
+        .collect()
+}
+
+/// Pack geohash strings into integers.
+fn pack<C: FromIterator<u64>>(hashes: impl IntoIterator<Item = String>) -> Result<C, Error> {
+    hashes
+        .into_iter()
+        .map(|h| h.parse::<PackedGeohash>().map(u64::from))
+        .collect()
+}
+
+/// Pack a geohash into an integer: its bits behind a leading 1 marking the
+/// precision, which fits a signed 64-bit (BIGINT) column.
+#[pyfunction]
+fn geohash_to_int(hash: &str) -> PyResult<u64> {
+    Ok(hash.parse::<PackedGeohash>()?.into())
+}
+
+/// The geohash string of a packed integer geohash.
+#[pyfunction]
+fn int_to_geohash(value: u64) -> PyResult<String> {
+    Ok(PackedGeohash::try_from(value)?.to_string())
+}
+
+/// `encode`, returning a packed integer geohash.
+#[pyfunction]
+fn encode_int(lng: f64, lat: f64, precision: usize) -> PyResult<u64> {
+    Ok(PackedGeohash::encode((lng, lat).into(), precision)?.into())
+}
+
+/// `encode_many`, returning packed integer geohashes (parallel).
+#[pyfunction]
+#[pyo3(signature = (lngs, lats, precision, num_threads=None))]
+fn encode_many_int(
+    py: Python<'_>,
+    lngs: Vec<f64>,
+    lats: Vec<f64>,
+    precision: usize,
+    num_threads: Option<usize>,
+) -> PyResult<Vec<u64>> {
+    if lngs.len() != lats.len() {
+        return Err(
+            Error::InvalidArgument("lngs and lats must have the same length".into()).into(),
+        );
+    }
+    let pool = make_pool(num_threads)?;
+    let raw: Result<Vec<u64>, Error> = py.allow_threads(|| {
+        run_with_pool(&pool, || {
+            lngs.into_par_iter()
+                .zip_eq(lats)
+                .map(|(lng, lat)| {
+                    PackedGeohash::encode((lng, lat).into(), precision).map(u64::from)
+                })
+                .collect()
+        })
+    });
+    Ok(raw?)
+}
+
+/// `decode_many` of packed integer geohashes, to (lng, lat) centres (parallel).
+#[pyfunction]
+#[pyo3(signature = (values, num_threads=None))]
+fn decode_many_int(
+    py: Python<'_>,
+    values: Vec<u64>,
+    num_threads: Option<usize>,
+) -> PyResult<Vec<(f64, f64)>> {
+    let pool = make_pool(num_threads)?;
+    let raw: Result<Vec<(f64, f64)>, Error> = py.allow_threads(|| {
+        run_with_pool(&pool, || {
+            values
+                .into_par_iter()
+                .map(|v| {
+                    let center = PackedGeohash::try_from(v)?.bbox().center();
+                    Ok((center.x, center.y))
+                })
+                .collect()
+        })
+    });
+    Ok(raw?)
+}
+
+/// `polygon_to_geohashes`, returning packed integer geohashes.
+///
+/// Polygons and multipolygons are walked on packed cells without building any
+/// strings; other geometries go through `polygon_to_geohashes`.
+#[pyfunction]
+#[pyo3(signature = (py_polygon, precision, inner=None, *, mode=None, min_overlap=None, max_cells=None))]
+fn polygon_to_geohashes_int(
+    py: Python,
+    py_polygon: Bound<'_, PyAny>,
+    precision: usize,
+    inner: Option<bool>,
+    mode: Option<&str>,
+    min_overlap: Option<f64>,
+    max_cells: Option<usize>,
+) -> PyResult<HashSet<u64>> {
+    let geometry = extract_geometry(&py_polygon)?;
+    let mode = geometry_coverage_mode(&geometry, inner, mode, min_overlap)?;
+    with_signal_checks(py, |interrupted| {
+        let polygons = match &geometry {
+            Geometry::Polygon(polygon) => vec![polygon.clone()],
+            Geometry::MultiPolygon(multi) => multi.0.clone(),
+            other => {
+                let hashes =
+                    geometry_to_geohashes_bounded(other, precision, mode, max_cells, interrupted)?;
+                return pack(hashes);
+            }
+        };
+        let packed = polygons_to_packed_geohashes_bounded(
+            polygons,
+            precision,
+            mode,
+            max_cells,
+            interrupted,
+        )?;
+        Ok(packed.into_iter().map(u64::from).collect())
+    })
+}
+
+/// `expand_geohashes` of packed integer geohashes, returning packed integers.
+///
+/// A group of one precision is expanded on packed cells without building any
+/// strings; `exact`, `compact` and mixed precisions go through
+/// `expand_geohashes`.
+#[pyfunction]
+#[pyo3(signature = (values, expansion_m, exact=false, compact=false))]
+fn expand_geohashes_int(
+    py: Python<'_>,
+    values: Vec<u64>,
+    expansion_m: f64,
+    exact: bool,
+    compact: bool,
+) -> PyResult<Vec<u64>> {
+    if values.is_empty() {
+        return Ok(vec![]);
+    }
+    let packed = values
+        .iter()
+        .map(|&v| PackedGeohash::try_from(v))
+        .collect::<Result<HashSet<_>, Error>>()?;
+    let precision = packed.iter().next().map(|h| h.precision());
+    if exact || compact || packed.iter().any(|h| Some(h.precision()) != precision) {
+        let hashes = expand_geohashes(py, unpack(values)?, expansion_m, exact, compact)?;
+        return Ok(pack(hashes)?);
+    }
+    check_distance_m("expansion_m", expansion_m)?;
+    let expanded =
+        py.allow_threads(|| expand_packed_geohash_set_by_distance(&packed, expansion_m))?;
+    Ok(expanded.into_iter().map(u64::from).collect())
+}
+
+// ── Module ────────────────────────────────────────────────────────────────────
+
+#[pymodule]
+fn geohash_polygon(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(polygon_to_geohashes, m)?)?;
+    m.add_function(wrap_pyfunction!(linestring_to_geohashes, m)?)?;
+    m.add_function(wrap_pyfunction!(polygons_to_geohash_mapping, m)?)?;
+    m.add_function(wrap_pyfunction!(polygon_to_geohash_weights, m)?)?;
+    m.add_function(wrap_pyfunction!(polygon_to_geohashes_compact, m)?)?;
+    m.add_function(wrap_pyfunction!(choose_precision_py, m)?)?;
+    m.add_function(wrap_pyfunction!(polygon_to_geohashes_auto, m)?)?;
+    m.add_function(wrap_pyfunction!(compact_geohashes, m)?)?;
+    m.add_function(wrap_pyfunction!(uncompact_geohashes, m)?)?;
+    m.add_function(wrap_pyfunction!(geohash_union_py, m)?)?;
+    m.add_function(wrap_pyfunction!(geohash_intersection_py, m)?)?;
+    m.add_function(wrap_pyfunction!(geohash_difference_py, m)?)?;
+    m.add_function(wrap_pyfunction!(geohash_contains_py, m)?)?;
+    m.add_function(wrap_pyfunction!(geohash_union_many, m)?)?;
+    m.add_function(wrap_pyfunction!(geohash_intersection_many, m)?)?;
+    m.add_function(wrap_pyfunction!(geohash_difference_many, m)?)?;
+    m.add_function(wrap_pyfunction!(geohash_contains_many, m)?)?;
+    m.add_function(wrap_pyfunction!(neighbors_py, m)?)?;
+    m.add_function(wrap_pyfunction!(neighbors_many, m)?)?;
+    m.add_function(wrap_pyfunction!(parent, m)?)?;
+    m.add_function(wrap_pyfunction!(parent_many, m)?)?;
+    m.add_function(wrap_pyfunction!(children, m)?)?;
+    m.add_function(wrap_pyfunction!(children_many, m)?)?;
+    m.add_function(wrap_pyfunction!(k_ring, m)?)?;
+    m.add_function(wrap_pyfunction!(ring, m)?)?;
+    m.add_function(wrap_pyfunction!(k_ring_many, m)?)?;
+    m.add_function(wrap_pyfunction!(ring_many, m)?)?;
+    m.add_function(wrap_pyfunction!(circle_to_geohashes_py, m)?)?;
+    m.add_function(wrap_pyfunction!(circles_to_geohashes_py, m)?)?;
+    m.add_function(wrap_pyfunction!(encode_py, m)?)?;
+    m.add_function(wrap_pyfunction!(encode_many, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_exactly, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_many, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_many_exactly, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_many_to_wkb, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_many_to_ewkb, m)?)?;
+    m.add_function(wrap_pyfunction!(geohashes_to_polygon, m)?)?;
+    m.add_function(wrap_pyfunction!(geohashes_to_polygon_wkb, m)?)?;
+    m.add_function(wrap_pyfunction!(geohashes_to_polygon_ewkb, m)?)?;
+    m.add_function(wrap_pyfunction!(expand_geohashes, m)?)?;
+    m.add_function(wrap_pyfunction!(expand_geohash_mapping, m)?)?;
+    m.add_function(wrap_pyfunction!(expand_geohashes_with_distance, m)?)?;
+    m.add_function(wrap_pyfunction!(geohash_to_int, m)?)?;
+    m.add_function(wrap_pyfunction!(int_to_geohash, m)?)?;
+    m.add_function(wrap_pyfunction!(encode_int, m)?)?;
+    m.add_function(wrap_pyfunction!(encode_many_int, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_many_int, m)?)?;
+    m.add_function(wrap_pyfunction!(polygon_to_geohashes_int, m)?)?;
+    m.add_function(wrap_pyfunction!(expand_geohashes_int, m)?)?;
+    m.add_function(wrap_pyfunction!(erode_geohashes, m)?)?;
+    m.add_function(wrap_pyfunction!(erode_geohash_mapping, m)?)?;
+    let py = m.py();
+    m.add("GeohashError", py.get_type::<GeohashError>())?;
+    m.add("InvalidGeohashError", py.get_type::<InvalidGeohashError>())?;
+    m.add(
+        "InvalidPrecisionError",
+        py.get_type::<InvalidPrecisionError>(),
+    )?;
+    m.add(
+        "InvalidCoordinateError",
+        py.get_type::<InvalidCoordinateError>(),
+    )?;
+    m.add("MixedPrecisionError", py.get_type::<MixedPrecisionError>())?;
+    m.add(
+        "InvalidGeometryError",
+        py.get_type::<InvalidGeometryError>(),
+    )?;
+    m.add("TooManyCellsError", py.get_type::<TooManyCellsError>())?;
+    Ok(())
+}
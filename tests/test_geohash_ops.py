@@ -1,5 +1,6 @@
-"""Tests for encode, decode_exactly, decode_many, encode_many, expand_geohash_mapping."""
+"""Tests for encode, decode_exactly, decode_many, encode_many, expand_geohash_mapping, erode_geohashes."""
 
+import array
 import math
 import struct
 import pytest
@@ -167,18 +168,33 @@ def test_expand_mapping_invalid_hash_raises():
         geohash_polygon.expand_geohash_mapping([["not_a_geohash!"]], 0.10)
 
 
-def test_expand_geohashes_mixed_precision_raises():
+def test_expand_geohashes_mixed_precision_expands_at_finest():
     h5 = geohash_polygon.encode(-73.5540, 45.5088, 5)
-    h7 = geohash_polygon.encode(-73.5540, 45.5088, 7)
-    with pytest.raises(ValueError, match="same precision"):
-        geohash_polygon.expand_geohashes([h5, h7], 100.0)
+    h7 = geohash_polygon.encode(-73.5000, 45.5088, 7)
+    expanded = set(geohash_polygon.expand_geohashes([h5, h7], 500.0))
+    assert {h5, h7} <= expanded
+    assert all(len(h) == 7 for h in expanded - {h5})
+    flat = geohash_polygon.uncompact_geohashes([h5, h7], 7)
+    expected = set(geohash_polygon.expand_geohashes(list(flat), 500.0))
+    assert geohash_polygon.uncompact_geohashes(expanded, 7) == expected
+
+
+def test_expand_geohashes_mixed_precision_compact():
+    h5 = geohash_polygon.encode(-73.5540, 45.5088, 5)
+    h7 = geohash_polygon.encode(-73.5000, 45.5088, 7)
+    expanded = geohash_polygon.expand_geohashes([h5, h7], 500.0, exact=True)
+    compact = geohash_polygon.expand_geohashes([h5, h7], 500.0, exact=True, compact=True)
+    assert set(compact) == geohash_polygon.compact_geohashes(expanded)
+    assert len(compact) < len(expanded)
 
 
-def test_expand_mapping_mixed_precision_raises():
+def test_expand_mapping_mixed_precision_matches_single_calls():
     h5 = geohash_polygon.encode(-73.5540, 45.5088, 5)
     h7 = geohash_polygon.encode(-73.5540, 45.5088, 7)
-    with pytest.raises(ValueError, match="same precision"):
-        geohash_polygon.expand_geohash_mapping([[h5, h7]], 100.0)
+    groups = [[h5, h7 + "0"], [h7]]
+    result = geohash_polygon.expand_geohash_mapping(groups, 100.0, compact=True)
+    for group, expanded in zip(groups, result):
+        assert set(expanded) == set(geohash_polygon.expand_geohashes(group, 100.0, compact=True))
 
 
 def test_expand_geohashes_empty():
@@ -313,6 +329,29 @@ def test_expand_mapping_two_groups_are_independent():
     assert set(result[0]).isdisjoint(set(result[1]))
 
 
+def test_expand_mapping_per_group_distances():
+    urban = [geohash_polygon.encode(-73.5540, 45.5088, 7)]
+    rural = [geohash_polygon.encode(-72.0, 46.0, 7)]
+    result = geohash_polygon.expand_geohash_mapping([urban, rural], [100.0, 1_000.0])
+    assert set(result[0]) == set(geohash_polygon.expand_geohashes(urban, 100.0))
+    assert set(result[1]) == set(geohash_polygon.expand_geohashes(rural, 1_000.0))
+    exact = geohash_polygon.expand_geohash_mapping([urban, rural], [100, 1_000], exact=True)
+    assert set(exact[1]) == set(geohash_polygon.expand_geohashes(rural, 1_000.0, exact=True))
+
+
+def test_expand_mapping_per_group_distances_length_mismatch_raises():
+    h = geohash_polygon.encode(-73.5540, 45.5088, 7)
+    with pytest.raises(ValueError, match="one distance per group"):
+        geohash_polygon.expand_geohash_mapping([[h], [h]], [100.0])
+    with pytest.raises(ValueError, match="one distance per group"):
+        geohash_polygon.expand_geohash_mapping([], [100.0])
+
+
+def test_expand_mapping_per_group_negative_distance_raises():
+    h = geohash_polygon.encode(-73.5540, 45.5088, 7)
+    with pytest.raises(ValueError, match="non-negative"):
+        geohash_polygon.expand_geohash_mapping([[h], [h]], [100.0, -5.0])
+
 # ── decode_many_to_wkb / decode_many_to_ewkb ─────────────────────────────────
 
 def test_decode_many_to_wkb_returns_bytes():
@@ -409,3 +448,544 @@ def test_decode_many_to_wkb_roundtrip_cluster():
     union = unary_union([loads(w) for w in wkb_list])
     result = set(geohash_polygon.polygon_to_geohashes(union, precision=6, inner=False))
     assert set(cluster).issubset(result)
+
+
+# ── compact_geohashes / uncompact_geohashes ───────────────────────────────────
+
+def test_compact_geohashes_merges_full_groups():
+    children = geohash_polygon.uncompact_geohashes(["f2h"], 5)
+    assert len(children) == 32 * 32
+    assert geohash_polygon.compact_geohashes(children | {"dr5"}) == {"f2h", "dr5"}
+
+
+def test_compact_geohashes_keeps_incomplete_groups():
+    children = geohash_polygon.uncompact_geohashes(["f2h"], 4) - {"f2h0"}
+    assert geohash_polygon.compact_geohashes(children) == children
+
+
+def test_compact_geohashes_drops_covered_descendants():
+    assert geohash_polygon.compact_geohashes(["f2h", "f2h3", "f2h3z"]) == {"f2h"}
+
+
+def test_compact_geohashes_invalid_raises():
+    with pytest.raises(ValueError):
+        geohash_polygon.compact_geohashes(["not_a_geohash!"])
+
+
+def test_uncompact_geohashes_finer_than_precision_raises():
+    with pytest.raises(ValueError, match="finer than the target precision"):
+        geohash_polygon.uncompact_geohashes(["dr5ru7"], 5)
+
+
+def test_uncompact_geohashes_is_bounded():
+    assert len(geohash_polygon.uncompact_geohashes(["f2h", "dr5ru"], 5, max_cells=1025)) == 1025
+    with pytest.raises(geohash_polygon.TooManyCellsError):
+        geohash_polygon.uncompact_geohashes(["f2h", "dr5ru"], 5, max_cells=1024)
+    # the default limit stops a runaway fan-out before it is built
+    with pytest.raises(geohash_polygon.TooManyCellsError):
+        geohash_polygon.uncompact_geohashes(["f"], 12)
+
+
+def test_compact_uncompact_roundtrip(polygon_verdun):
+    hashes = geohash_polygon.polygon_to_geohashes(polygon_verdun, 7, False)
+    compact = geohash_polygon.compact_geohashes(hashes)
+    assert len(compact) < len(hashes)
+    assert geohash_polygon.uncompact_geohashes(compact, 7) == hashes
+
+
+def test_compact_geohashes_with_explicit_threads():
+    hashes = geohash_polygon.uncompact_geohashes(["f2h", "dr5r"], 6)
+    r1 = geohash_polygon.compact_geohashes(hashes, num_threads=1)
+    r4 = geohash_polygon.compact_geohashes(hashes, num_threads=4)
+    assert r1 == r4 == {"f2h", "dr5r"}
+
+
+# ── geohashes_to_polygon ──────────────────────────────────────────────────────
+
+def test_geohashes_to_polygon_dissolves_cells(polygon_verdun):
+    from shapely.geometry import shape
+    from shapely.wkb import loads
+    from shapely.ops import unary_union
+
+    hashes = geohash_polygon.polygon_to_geohashes(polygon_verdun, 7, False)
+    dissolved = shape(geohash_polygon.geohashes_to_polygon(hashes))
+    boxes = unary_union([loads(w) for w in geohash_polygon.decode_many_to_wkb(list(hashes))])
+    assert dissolved.geom_type == "MultiPolygon"
+    assert dissolved.symmetric_difference(boxes).area < 1e-12
+
+
+def test_geohashes_to_polygon_keeps_holes():
+    from shapely.geometry import shape
+
+    center = geohash_polygon.encode(-73.554, 45.508, 6)
+    ring = set(geohash_polygon.expand_geohashes([center], 100.0)) - {center}
+    dissolved = shape(geohash_polygon.geohashes_to_polygon(ring))
+    assert len(dissolved.geoms) == 1
+    assert len(dissolved.geoms[0].interiors) == 1
+
+
+def test_geohashes_to_polygon_mixed_precision():
+    from shapely.geometry import shape
+
+    mixed = {"f2h", "f2h3z", "dr5ru"}
+    dissolved = shape(geohash_polygon.geohashes_to_polygon(mixed))
+    assert len(dissolved.geoms) == 2
+    uncompacted = shape(geohash_polygon.geohashes_to_polygon(geohash_polygon.uncompact_geohashes(mixed, 5)))
+    assert dissolved.symmetric_difference(uncompacted).area < 1e-12
+
+
+def test_geohashes_to_polygon_wkb_matches_geo_interface():
+    from shapely.geometry import shape
+    from shapely.wkb import loads
+
+    hashes = geohash_polygon.uncompact_geohashes(["f2h3", "f2h6"], 6)
+    from_wkb = loads(geohash_polygon.geohashes_to_polygon_wkb(hashes))
+    from_dict = shape(geohash_polygon.geohashes_to_polygon(hashes))
+    assert from_wkb.equals(from_dict)
+
+
+def test_geohashes_to_polygon_ewkb_srid():
+    ewkb = geohash_polygon.geohashes_to_polygon_ewkb(["f2h"], srid=32632)
+    assert struct.unpack_from("<I", ewkb, 1)[0] == 6 | 0x20000000
+    assert struct.unpack_from("<I", ewkb, 5)[0] == 32632
+
+
+def test_geohashes_to_polygon_invalid_raises():
+    with pytest.raises(ValueError):
+        geohash_polygon.geohashes_to_polygon(["not_a_geohash!"])
+
+
+# ── circle_to_geohashes / circles_to_geohashes ────────────────────────────────
+
+def _cell_min_dist_m(lng, lat, h):
+    """Haversine distance from (lng, lat) to the closest of 400 points on the cell boundary."""
+    c_lng, c_lat, lng_err, lat_err = geohash_polygon.decode_exactly(h)
+    x0, x1, y0, y1 = c_lng - lng_err, c_lng + lng_err, c_lat - lat_err, c_lat + lat_err
+    if x0 <= lng <= x1 and y0 <= lat <= y1:
+        return 0.0
+    ts = [i / 100 for i in range(101)]
+    edge = ([(x0 + (x1 - x0) * t, y0) for t in ts] + [(x0 + (x1 - x0) * t, y1) for t in ts]
+            + [(x0, y0 + (y1 - y0) * t) for t in ts] + [(x1, y0 + (y1 - y0) * t) for t in ts])
+    return min(haversine_m(lng, lat, x, y) for x, y in edge)
+
+
+@pytest.mark.parametrize("lng, lat", [(-73.554, 45.508), (-135.05, 60.72), (25.0, 78.2)])
+def test_circle_to_geohashes_within_radius(lng, lat):
+    radius_m = 800.0
+    cells = geohash_polygon.circle_to_geohashes(lng, lat, radius_m, 7, False)
+    assert geohash_polygon.encode(lng, lat, 7) in cells
+    for h in cells:
+        # 1% slack for haversine vs. ellipsoidal geodesic distance
+        assert _cell_min_dist_m(lng, lat, h) <= radius_m * 1.01
+    # Every neighbour just outside the result is beyond the radius.
+    ring = set(geohash_polygon.expand_geohashes(list(cells), 1.0)) - cells
+    for h in ring:
+        assert _cell_min_dist_m(lng, lat, h) >= radius_m * 0.99
+
+
+def test_circle_to_geohashes_inner_subset():
+    outer = geohash_polygon.circle_to_geohashes(-73.554, 45.508, 800.0, 7, False)
+    inner = geohash_polygon.circle_to_geohashes(-73.554, 45.508, 800.0, 7, True)
+    assert inner and inner < outer
+
+
+def test_circle_to_geohashes_crosses_antimeridian():
+    cells = geohash_polygon.circle_to_geohashes(179.999, 0.0, 1000.0, 7, False)
+    lngs = [lng for lng, _ in geohash_polygon.decode_many(list(cells))]
+    assert any(lng > 179.9 for lng in lngs)
+    assert any(lng < -179.9 for lng in lngs)
+
+
+@pytest.mark.parametrize("radius_m", [-1.0, float("nan"), float("inf")])
+def test_circle_to_geohashes_invalid_radius_raises(radius_m):
+    with pytest.raises(ValueError, match="radius_m"):
+        geohash_polygon.circle_to_geohashes(-73.554, 45.508, radius_m, 7, False)
+
+
+def test_circles_to_geohashes_matches_single_calls():
+    lngs, lats, radii = [-73.554, -87.6298], [45.508, 41.8781], [300.0, 800.0]
+    result = geohash_polygon.circles_to_geohashes(lngs, lats, radii, 7, False, num_threads=2)
+    assert result == [
+        geohash_polygon.circle_to_geohashes(lng, lat, r, 7, False)
+        for lng, lat, r in zip(lngs, lats, radii)
+    ]
+
+
+def test_circles_to_geohashes_mismatched_lengths_raises():
+    with pytest.raises(ValueError, match="same length"):
+        geohash_polygon.circles_to_geohashes([-73.0, -74.0], [45.0, 45.0], [100.0], 7, False)
+
+
+# ── polar regions ─────────────────────────────────────────────────────────────
+
+@pytest.mark.parametrize("lat", [89.0, -89.0])
+def test_expand_geohashes_near_pole_is_bounded(lat):
+    center = geohash_polygon.encode(25.0, lat, 5)
+    expanded = geohash_polygon.expand_geohashes([center], 50_000.0)
+    centers = geohash_polygon.decode_many(expanded)
+    # no wrap onto the other hemisphere, and nothing far from the origin's latitude
+    assert all(c_lat * lat > 0 and abs(c_lat - lat) < 1.0 for _, c_lat in centers)
+    # the pole is 111 km away, beyond reach: rows near the origin are not full rings
+    assert len(expanded) < 2 ** 13 * 10
+
+
+@pytest.mark.parametrize("lat", [89.9, -89.9])
+def test_expand_geohashes_reaches_across_pole(lat):
+    center = geohash_polygon.encode(25.0, lat, 4)
+    expanded = set(geohash_polygon.expand_geohashes([center], 20_000.0))
+    # the cell on the opposite side of the pole is only ~22 km away through it
+    assert geohash_polygon.encode(-155.0, lat, 4) in expanded
+
+# ── typed exceptions ──────────────────────────────────────────────────────────
+
+@pytest.mark.parametrize(
+    "call, exception",
+    [
+        (lambda: geohash_polygon.decode_exactly("dra"), geohash_polygon.InvalidGeohashError),
+        (lambda: geohash_polygon.encode(0.0, 0.0, 13), geohash_polygon.InvalidPrecisionError),
+        (lambda: geohash_polygon.neighbors("s" * 13), geohash_polygon.InvalidGeohashError),
+        (lambda: geohash_polygon.encode(0.0, 95.0, 5), geohash_polygon.InvalidCoordinateError),
+        (
+            lambda: geohash_polygon.erode_geohashes(["f2h", "f2h3"], 10.0),
+            geohash_polygon.MixedPrecisionError,
+        ),
+        (
+            lambda: geohash_polygon.polygon_to_geohashes(None, 5, True),
+            geohash_polygon.InvalidGeometryError,
+        ),
+    ],
+)
+def test_errors_raise_specific_exceptions(call, exception):
+    with pytest.raises(exception):
+        call()
+
+
+def test_exception_hierarchy():
+    for name in [
+        "InvalidGeohashError",
+        "InvalidPrecisionError",
+        "InvalidCoordinateError",
+        "MixedPrecisionError",
+        "InvalidGeometryError",
+        "TooManyCellsError",
+    ]:
+        assert issubclass(getattr(geohash_polygon, name), geohash_polygon.GeohashError)
+    assert issubclass(geohash_polygon.GeohashError, ValueError)
+
+
+@pytest.mark.parametrize(
+    "call",
+    [
+        lambda: geohash_polygon.circle_to_geohashes(0.0, 0.0, -1.0, 5, True),
+        lambda: geohash_polygon.linestring_to_geohashes(None, 5, -1.0),
+        lambda: geohash_polygon.expand_geohashes(["f2h"], float("nan")),
+        lambda: geohash_polygon.expand_geohashes_with_distance(["f2h"], 1.0, bands=[2.0, 1.0]),
+        lambda: geohash_polygon.encode_many([0.0], [0.0, 1.0], 5),
+    ],
+)
+def test_bad_arguments_raise_geohash_error(call):
+    with pytest.raises(geohash_polygon.GeohashError):
+        call()
+
+# ── exact expansion ───────────────────────────────────────────────────────────
+
+def test_expand_geohashes_exact_is_round():
+    center = geohash_polygon.encode(-73.5540, 45.5088, 7)
+    lng0, lat0 = geohash_polygon.decode_many([center])[0]
+    square = set(geohash_polygon.expand_geohashes([center], 2_000.0))
+    exact = set(geohash_polygon.expand_geohashes([center], 2_000.0, exact=True))
+    assert exact < square
+    # cells are ~110 x 150 m: their centres are within half a diagonal of the nearest edge
+    slack = 2 * 95
+    for lng, lat in geohash_polygon.decode_many(list(exact)):
+        assert haversine_m(lng0, lat0, lng, lat) <= 2_000.0 + slack
+    dropped = geohash_polygon.decode_many(list(square - exact))
+    assert all(haversine_m(lng0, lat0, lng, lat) > 2_000.0 for lng, lat in dropped)
+
+
+def test_expand_geohash_mapping_exact_matches_single_calls():
+    groups = [
+        [geohash_polygon.encode(-73.5540, 45.5088, 6)],
+        [geohash_polygon.encode(2.35, 48.85, 7), geohash_polygon.encode(2.36, 48.85, 7)],
+    ]
+    mapped = geohash_polygon.expand_geohash_mapping(groups, 1_500.0, exact=True)
+    for group, result in zip(groups, mapped):
+        assert set(result) == set(geohash_polygon.expand_geohashes(group, 1_500.0, exact=True))
+
+def test_expand_geohashes_with_distance_matches_exact():
+    center = geohash_polygon.encode(-73.5540, 45.5088, 7)
+    distances = geohash_polygon.expand_geohashes_with_distance([center], 1_000.0)
+    exact = set(geohash_polygon.expand_geohashes([center], 1_000.0, exact=True))
+    assert set(distances) == exact - {center}
+    assert all(0.0 <= d <= 1_000.0 for d in distances.values())
+    # touching neighbours are at distance 0
+    assert sum(d == 0.0 for d in distances.values()) == 8
+
+
+def test_expand_geohashes_with_distance_bands():
+    center = geohash_polygon.encode(-73.5540, 45.5088, 7)
+    distances = geohash_polygon.expand_geohashes_with_distance([center], 1_500.0)
+    bands = geohash_polygon.expand_geohashes_with_distance(
+        [center], 1_500.0, bands=[250, 500, 1000]
+    )
+    assert set(bands) == set(distances)
+    for h, band in bands.items():
+        d = distances[h]
+        assert band == sum(d > b for b in [250, 500, 1000])
+    assert set(bands.values()) == {0, 1, 2, 3}
+
+
+@pytest.mark.parametrize("bands", [[500, 250], [-1.0], [float("nan")]])
+def test_expand_geohashes_with_distance_rejects_bad_bands(bands):
+    with pytest.raises(ValueError, match="bands"):
+        geohash_polygon.expand_geohashes_with_distance(["f25dvz3"], 1_000.0, bands=bands)
+
+
+def test_expand_geohashes_with_distance_hops():
+    center = geohash_polygon.encode(-73.5540, 45.5088, 7)
+    distances = geohash_polygon.expand_geohashes_with_distance([center], 1_000.0)
+    hops = geohash_polygon.expand_geohashes_with_distance([center], 1_000.0, hops=True)
+    assert set(hops) == set(distances)
+    # the touching neighbours form the first ring
+    assert {h for h, hop in hops.items() if hop == 1} == set(geohash_polygon.neighbors(center))
+    for h, hop in hops.items():
+        if hop > 1:
+            ring = set(geohash_polygon.neighbors(h))
+            assert min(hops[n] for n in ring if n in hops) == hop - 1
+    with pytest.raises(ValueError, match="hops"):
+        geohash_polygon.expand_geohashes_with_distance([center], 1_000.0, bands=[1.0], hops=True)
+
+# ── erosion ───────────────────────────────────────────────────────────────────
+
+def test_erode_geohashes_undoes_expansion():
+    center = geohash_polygon.encode(-73.5540, 45.5088, 7)
+    expanded = geohash_polygon.expand_geohashes([center], 1_000.0)
+    assert set(geohash_polygon.erode_geohashes(expanded, 1_000.0)) == {center}
+    assert set(geohash_polygon.erode_geohashes(expanded, 0.0)) == set(expanded)
+    assert geohash_polygon.erode_geohashes(expanded, 5_000.0) == []
+
+
+def test_erode_geohashes_exact_keeps_more_than_hops():
+    center = geohash_polygon.encode(-73.5540, 45.5088, 7)
+    block = geohash_polygon.expand_geohashes([center], 2_000.0)
+    hops = set(geohash_polygon.erode_geohashes(block, 500.0))
+    exact = set(geohash_polygon.erode_geohashes(block, 500.0, exact=True))
+    assert center in hops and hops <= exact <= set(block)
+
+
+def test_erode_geohash_mapping_matches_single_calls():
+    groups = [
+        geohash_polygon.expand_geohashes([geohash_polygon.encode(-73.5540, 45.5088, 6)], 3_000.0),
+        [],
+        geohash_polygon.expand_geohashes([geohash_polygon.encode(2.35, 48.85, 7)], 800.0),
+    ]
+    mapped = geohash_polygon.erode_geohash_mapping(groups, 400.0)
+    for group, result in zip(groups, mapped):
+        assert set(result) == set(geohash_polygon.erode_geohashes(group, 400.0))
+
+
+def test_erode_geohashes_rejects_bad_arguments():
+    with pytest.raises(ValueError, match="erosion_m must be a finite non-negative"):
+        geohash_polygon.erode_geohashes(["f25dvz3"], -1.0)
+    with pytest.raises(geohash_polygon.MixedPrecisionError):
+        geohash_polygon.erode_geohash_mapping([["f25dvz3", "f25dvz"]], 100.0)
+
+# ── set algebra ───────────────────────────────────────────────────────────────
+
+def test_geohash_set_algebra_understands_prefixes():
+    a = {"f2h", "f2j3", "dr5r"}
+    b = ["f2h0", "f2h1k", "f2j", "9q8y"]
+    assert geohash_polygon.geohash_union(a, b) == {"f2h", "f2j", "dr5r", "9q8y"}
+    assert geohash_polygon.geohash_intersection(a, b) == {"f2h0", "f2h1k", "f2j3"}
+    difference = geohash_polygon.geohash_difference(a, b)
+    assert "dr5r" in difference and "f2h" not in difference
+    flat = lambda s: geohash_polygon.uncompact_geohashes(s, 5)
+    assert flat(difference) == flat(a) - flat(b)
+    assert geohash_polygon.geohash_contains(a, ["f2h1k", "f2j3z"])
+    assert not geohash_polygon.geohash_contains(a, ["f2j"])
+
+
+def test_geohash_set_algebra_many_matches_single_calls():
+    lefts = [{"f2h"}, ["dr5r", "dr5x"], set()]
+    rights = [["f2h3z"], {"dr5"}, {"9q8y"}]
+    for many, single in [
+        (geohash_polygon.geohash_union_many, geohash_polygon.geohash_union),
+        (geohash_polygon.geohash_intersection_many, geohash_polygon.geohash_intersection),
+        (geohash_polygon.geohash_difference_many, geohash_polygon.geohash_difference),
+        (geohash_polygon.geohash_contains_many, geohash_polygon.geohash_contains),
+    ]:
+        assert many(lefts, rights, num_threads=2) == [single(a, b) for a, b in zip(lefts, rights)]
+
+
+def test_geohash_set_algebra_errors():
+    with pytest.raises(ValueError, match="same length"):
+        geohash_polygon.geohash_union_many([{"f2h"}], [])
+    with pytest.raises(geohash_polygon.InvalidGeohashError):
+        geohash_polygon.geohash_intersection({"f2h"}, {"f2a"})
+
+# ── neighbours / parent / children ────────────────────────────────────────────
+
+def test_neighbors_surround_the_cell():
+    h = geohash_polygon.encode(-73.5540, 45.5088, 7)
+    nbrs = geohash_polygon.neighbors(h)
+    assert len(nbrs) == 8 and h not in nbrs
+    assert set(geohash_polygon.expand_geohashes([h], 100.0)) == set(nbrs) | {h}
+    polar = geohash_polygon.encode(0.0, 89.99, 4)
+    assert len(geohash_polygon.neighbors(polar)) == 5
+
+
+def test_parent_and_children():
+    assert geohash_polygon.parent("f25dvz3") == "f25dvz"
+    assert geohash_polygon.parent("f25dvz3", 3) == "f25"
+    kids = geohash_polygon.children("f25")
+    assert len(kids) == 32 and kids[0] == "f250" and kids[-1] == "f25z"
+    assert len(geohash_polygon.children("f25", 5)) == 1024
+    with pytest.raises(geohash_polygon.InvalidPrecisionError):
+        geohash_polygon.parent("f")
+    with pytest.raises(geohash_polygon.InvalidPrecisionError):
+        geohash_polygon.parent("f25", 4)
+    with pytest.raises(geohash_polygon.InvalidPrecisionError):
+        geohash_polygon.children("f25dv", 3)
+    with pytest.raises(geohash_polygon.InvalidGeohashError):
+        geohash_polygon.children("f2a")
+
+
+def test_children_fan_out_is_bounded():
+    assert len(geohash_polygon.children("f2", 4, max_cells=1024)) == 1024
+    with pytest.raises(geohash_polygon.TooManyCellsError):
+        geohash_polygon.children("f2", 4, max_cells=1023)
+    # the default limit stops a runaway fan-out before it is built
+    with pytest.raises(geohash_polygon.TooManyCellsError):
+        geohash_polygon.children("f", 12)
+    with pytest.raises(geohash_polygon.TooManyCellsError):
+        geohash_polygon.children_many(["f2", "dr"], 5, max_cells=1000)
+
+
+def test_hierarchy_many_matches_single_calls():
+    hashes = ["f25dvz3", "dr5r", "9q8yy"]
+    assert geohash_polygon.neighbors_many(hashes, num_threads=2) == [
+        geohash_polygon.neighbors(h) for h in hashes
+    ]
+    assert geohash_polygon.parent_many(hashes) == [geohash_polygon.parent(h) for h in hashes]
+    assert geohash_polygon.parent_many(hashes, 2) == ["f2", "dr", "9q"]
+    assert geohash_polygon.children_many(hashes[1:], 6) == [
+        geohash_polygon.children(h, 6) for h in hashes[1:]
+    ]
+
+# ── k-rings ───────────────────────────────────────────────────────────────────
+
+def test_k_ring_and_ring():
+    h = geohash_polygon.encode(-73.5540, 45.5088, 7)
+    assert geohash_polygon.k_ring(h, 0) == [h]
+    assert set(geohash_polygon.k_ring(h, 1)) == set(geohash_polygon.neighbors(h)) | {h}
+    assert len(geohash_polygon.k_ring(h, 3)) == 49
+    ring = geohash_polygon.ring(h, 3)
+    assert len(ring) == 24
+    assert set(ring) == set(geohash_polygon.k_ring(h, 3)) - set(geohash_polygon.k_ring(h, 2))
+
+
+def test_k_ring_many_matches_single_calls():
+    hashes = ["f25dvz3", "dr5r", geohash_polygon.encode(0.0, 89.99, 4)]
+    assert geohash_polygon.k_ring_many(hashes, 2, num_threads=2) == [
+        geohash_polygon.k_ring(h, 2) for h in hashes
+    ]
+    assert geohash_polygon.ring_many(hashes, 2) == [geohash_polygon.ring(h, 2) for h in hashes]
+    with pytest.raises(geohash_polygon.InvalidGeohashError):
+        geohash_polygon.k_ring_many(["f2a"], 1)
+
+
+def test_k_ring_size_is_bounded():
+    h = geohash_polygon.encode(-73.5540, 45.5088, 7)
+    assert len(geohash_polygon.k_ring(h, 3, max_cells=49)) == 49
+    with pytest.raises(geohash_polygon.TooManyCellsError):
+        geohash_polygon.k_ring(h, 3, max_cells=48)
+    with pytest.raises(geohash_polygon.TooManyCellsError):
+        geohash_polygon.ring(h, 3, max_cells=23)
+    # the default limit stops a runaway ring before it is built
+    with pytest.raises(geohash_polygon.TooManyCellsError):
+        geohash_polygon.k_ring(h, 1_000)
+    with pytest.raises(geohash_polygon.TooManyCellsError):
+        geohash_polygon.ring_many([h, "dr5r"], 2, max_cells=15)
+    # past the default with no limit at all
+    assert len(geohash_polygon.k_ring_many([h], 501, max_cells=None)[0]) == 1003**2
+
+# ── packed integer geohashes ──────────────────────────────────────────────────
+
+def test_geohash_to_int_round_trips():
+    for h in ["f", "f25dvz3", "dr5ru7c1fb0z"]:
+        value = geohash_polygon.geohash_to_int(h)
+        assert 0 < value < 2**63
+        assert geohash_polygon.int_to_geohash(value) == h
+    # a parent is its child shifted right by 5 bits
+    assert geohash_polygon.geohash_to_int("f25dvz3") >> 5 == geohash_polygon.geohash_to_int("f25dvz")
+    # the same bits at another precision are another value
+    assert geohash_polygon.geohash_to_int("0") != geohash_polygon.geohash_to_int("00")
+    with pytest.raises(geohash_polygon.InvalidGeohashError):
+        geohash_polygon.int_to_geohash(3)
+
+
+def test_int_variants_match_strings():
+    lngs, lats = [-73.554, 2.35, 179.99], [45.508, 48.85, -89.99]
+    hashes = geohash_polygon.encode_many(lngs, lats, 9)
+    values = geohash_polygon.encode_many_int(lngs, lats, 9, num_threads=2)
+    assert values == [geohash_polygon.geohash_to_int(h) for h in hashes]
+    assert geohash_polygon.encode_int(lngs[0], lats[0], 9) == values[0]
+    assert geohash_polygon.decode_many_int(values) == geohash_polygon.decode_many(hashes)
+    # one precision, mixed precisions, and the exact and compact variants
+    groups = [hashes[:2], [h[:7] for h in hashes[:2]] + ["f25dvz"]]
+    for group in groups:
+        for exact in [False, True]:
+            for compact in [False, True]:
+                expanded = geohash_polygon.expand_geohashes_int(
+                    [geohash_polygon.geohash_to_int(h) for h in group], 50.0, exact, compact
+                )
+                assert {geohash_polygon.int_to_geohash(v) for v in expanded} == set(
+                    geohash_polygon.expand_geohashes(group, 50.0, exact, compact)
+                )
+    assert geohash_polygon.expand_geohashes_int([], -1.0) == []
+    with pytest.raises(ValueError, match="non-negative"):
+        geohash_polygon.expand_geohashes_int(values, -1.0)
+
+# ── NumPy arrays ──────────────────────────────────────────────────────────────
+
+def test_encode_many_numpy_arrays():
+    np = pytest.importorskip("numpy")
+    lngs = np.array([-73.554, 2.35, 179.99])
+    lats = np.array([45.508, 48.85, -89.99])
+    hashes = geohash_polygon.encode_many(lngs, lats, 9, num_threads=2)
+    assert isinstance(hashes, np.ndarray) and hashes.dtype == np.dtype("S9")
+    assert [h.decode() for h in hashes] == geohash_polygon.encode_many(list(lngs), list(lats), 9)
+    # strided views and other float dtypes are accepted too
+    strided = geohash_polygon.encode_many(np.repeat(lngs, 2)[::2], lats.astype(np.float32), 5)
+    assert [h.decode() for h in strided] == [h.decode()[:5] for h in hashes]
+    with pytest.raises(ValueError, match="same length"):
+        geohash_polygon.encode_many(lngs, lats[:2], 9)
+    with pytest.raises(geohash_polygon.InvalidCoordinateError):
+        geohash_polygon.encode_many(lngs, np.array([0.0, 95.0, 0.0]), 9)
+
+
+def test_encode_many_other_buffers_give_lists():
+    # float64 buffers that are not NumPy arrays are read without NumPy
+    lngs, lats = array.array("d", [-73.554, 2.35]), array.array("d", [45.508, 48.85])
+    expected = geohash_polygon.encode_many(list(lngs), list(lats), 9)
+    assert geohash_polygon.encode_many(lngs, lats, 9) == expected
+    assert geohash_polygon.encode_many(memoryview(lngs), list(lats), 9) == expected
+    assert geohash_polygon.encode_many(memoryview(lngs)[::-1], lats, 9) == [
+        geohash_polygon.encode(2.35, 45.508, 9),
+        geohash_polygon.encode(-73.554, 48.85, 9),
+    ]
+
+
+def test_decode_many_numpy_arrays():
+    np = pytest.importorskip("numpy")
+    hashes = ["f25dyhrx7", "u09tv", "pbpbpcbdq"]
+    expected = np.array(geohash_polygon.decode_many(hashes))
+    for array in [np.array(hashes, dtype="S12"), np.array(hashes)]:
+        centers = geohash_polygon.decode_many(array)
+        assert centers.shape == (3, 2) and centers.dtype == np.float64
+        np.testing.assert_array_equal(centers, expected)
+    # round trip through the array encoder
+    encoded = geohash_polygon.encode_many(expected[:, 0].copy(), expected[:, 1].copy(), 12)
+    assert geohash_polygon.decode_many(encoded).shape == (3, 2)
+    with pytest.raises(geohash_polygon.InvalidGeohashError):
+        geohash_polygon.decode_many(np.array(["f2a"], dtype="S12"))
@@ -67,13 +67,13 @@ def test_multipolygon_regression_issue_18():
         ([1, 2, 3], 0),
         ((1, 2, 3), 0),
         ({}, 0),
-        (shapely.geometry.Point((-99.1795917, 19.432134)), 1),
+        (shapely.geometry.LinearRing([(0, 0), (1, 0), (1, 1)]), 1),
     ],
 )
 def test_exception_when_invalid(polygon, exception_message_idx):
     exception_messages = [
-        r"Object does not implement __geo_interface__. Expected a Shapely Polygon or MultiPolygon.*",
-        r"The geometry is not a Polygon or MultiPolygon",
+        r"Object does not implement __geo_interface__. Expected a Shapely geometry.*",
+        r"Unsupported geometry type: LinearRing",
     ]
     with pytest.raises(
         ValueError,
@@ -214,3 +214,395 @@ def test_hole(level, inner, polygon_hole):
     assert geohash_polygon.polygon_to_geohashes(
         polygon_hole, level, inner
     ) == polygon_to_geohashes_py(polygon_hole, level, inner)
+
+
+_BASE32 = "0123456789bcdefghjkmnpqrstuvwxyz"
+
+
+def _explode(hashes, precision):
+    """Replace every hash with all of its descendants at `precision`."""
+    out = set()
+    pending = list(hashes)
+    while pending:
+        h = pending.pop()
+        if len(h) == precision:
+            out.add(h)
+        else:
+            pending.extend(h + c for c in _BASE32)
+    return out
+
+
+@pytest.mark.parametrize("inner", [False, True])
+def test_compact_matches_fixed_precision(inner, polygon_verdun):
+    compact = geohash_polygon.polygon_to_geohashes_compact(polygon_verdun, 4, 7, inner)
+    assert _explode(compact, 7) == geohash_polygon.polygon_to_geohashes(polygon_verdun, 7, inner)
+
+
+@pytest.mark.parametrize("inner", [False, True])
+def test_compact_with_hole(inner, polygon_hole):
+    compact = geohash_polygon.polygon_to_geohashes_compact(polygon_hole, 3, 6, inner)
+    assert _explode(compact, 6) == geohash_polygon.polygon_to_geohashes(polygon_hole, 6, inner)
+
+
+def test_compact_is_smaller_and_mixed_precision(polygon_whitehorse):
+    compact = geohash_polygon.polygon_to_geohashes_compact(polygon_whitehorse, 3, 6, False)
+    fixed = geohash_polygon.polygon_to_geohashes(polygon_whitehorse, 6, False)
+    assert len(compact) < len(fixed)
+    assert {len(h) for h in compact} > {6}
+
+
+def test_compact_invalid_precision_range_raises(polygon_verdun):
+    with pytest.raises(ValueError):
+        geohash_polygon.polygon_to_geohashes_compact(polygon_verdun, 7, 5, False)
+
+
+@pytest.mark.parametrize("inner", [False, True])
+def test_polygons_to_geohash_mapping_matches_single_calls(
+    inner, polygon_verdun, polygon_hole, polygon_crescent
+):
+    polygons = [polygon_verdun, polygon_hole, polygon_crescent]
+    result = geohash_polygon.polygons_to_geohash_mapping(polygons, 5, inner)
+    assert result == [geohash_polygon.polygon_to_geohashes(p, 5, inner) for p in polygons]
+
+
+def test_polygons_to_geohash_mapping_with_explicit_threads(polygon_verdun, polygon_hole):
+    polygons = [polygon_verdun, polygon_hole]
+    r1 = geohash_polygon.polygons_to_geohash_mapping(polygons, 6, False, num_threads=1)
+    r4 = geohash_polygon.polygons_to_geohash_mapping(polygons, 6, False, num_threads=4)
+    assert r1 == r4
+
+
+def test_polygons_to_geohash_mapping_empty():
+    assert geohash_polygon.polygons_to_geohash_mapping([], 5, False) == []
+
+
+def test_polygons_to_geohash_mapping_invalid_geometry_raises(polygon_verdun):
+    with pytest.raises(ValueError, match="__geo_interface__"):
+        geohash_polygon.polygons_to_geohash_mapping([polygon_verdun, None], 5, False)
+
+
+def test_weights_keys_and_values(polygon_hole):
+    weights = geohash_polygon.polygon_to_geohash_weights(polygon_hole, 6)
+    outer = geohash_polygon.polygon_to_geohashes(polygon_hole, 6, False)
+    inner = geohash_polygon.polygon_to_geohashes(polygon_hole, 6, True)
+    assert set(weights) <= outer
+    assert {h for h, w in weights.items() if w == 1.0} == inner
+    assert all(0.0 < w <= 1.0 for w in weights.values())
+
+
+def test_weights_match_shapely_overlap(polygon_verdun):
+    from shapely.wkb import loads
+
+    weights = geohash_polygon.polygon_to_geohash_weights(polygon_verdun, 6)
+    hashes = list(weights)
+    for h, wkb in zip(hashes, geohash_polygon.decode_many_to_wkb(hashes)):
+        cell = loads(wkb)
+        expected = cell.intersection(polygon_verdun).area / cell.area
+        assert weights[h] == pytest.approx(expected, abs=1e-9)
+
+
+def test_weights_sum_to_polygon_area(polygon_whitehorse):
+    from shapely.wkb import loads
+
+    weights = geohash_polygon.polygon_to_geohash_weights(polygon_whitehorse, 5)
+    hashes = list(weights)
+    areas = [loads(w).area for w in geohash_polygon.decode_many_to_wkb(hashes)]
+    covered = sum(weights[h] * a for h, a in zip(hashes, areas))
+    assert covered == pytest.approx(polygon_whitehorse.area, rel=1e-9)
+
+
+@pytest.mark.parametrize("inner, mode", [(True, "contained"), (False, "intersects")])
+def test_mode_matches_inner(inner, mode, polygon_hole):
+    assert geohash_polygon.polygon_to_geohashes(
+        polygon_hole, 6, mode=mode
+    ) == geohash_polygon.polygon_to_geohashes(polygon_hole, 6, inner)
+
+
+def test_modes_are_nested(polygon_verdun):
+    def cover(mode, **kwargs):
+        return geohash_polygon.polygon_to_geohashes(polygon_verdun, 6, mode=mode, **kwargs)
+
+    contained = cover("contained")
+    centroid = cover("centroid")
+    half = cover("min_overlap", min_overlap=0.5)
+    intersects = cover("intersects")
+    assert contained <= centroid <= intersects
+    assert contained <= half <= intersects
+    assert cover("min_overlap", min_overlap=1.0) == contained
+
+
+def test_min_overlap_matches_weights(polygon_verdun):
+    weights = geohash_polygon.polygon_to_geohash_weights(polygon_verdun, 6)
+    expected = {h for h, w in weights.items() if w >= 0.25}
+    assert geohash_polygon.polygon_to_geohashes(
+        polygon_verdun, 6, mode="min_overlap", min_overlap=0.25
+    ) == expected
+
+
+def test_centroid_mode_matches_shapely(polygon_hole):
+    result = geohash_polygon.polygon_to_geohashes(polygon_hole, 6, mode="centroid")
+    outer = geohash_polygon.polygon_to_geohashes(polygon_hole, 6, False)
+    centers = dict(zip(outer, geohash_polygon.decode_many(list(outer))))
+    expected = {h for h, c in centers.items() if polygon_hole.intersects(shapely.geometry.Point(c))}
+    assert result == expected
+
+
+@pytest.mark.parametrize(
+    "kwargs, expected_message",
+    [
+        ({}, r"one of inner or mode is required"),
+        ({"inner": True, "mode": "contained"}, r"either inner or mode, not both"),
+        ({"mode": "nearest"}, r"unknown mode"),
+        ({"mode": "min_overlap"}, r"between 0 and 1"),
+        ({"mode": "min_overlap", "min_overlap": 1.5}, r"between 0 and 1"),
+        ({"mode": "centroid", "min_overlap": 0.5}, r"only valid with mode='min_overlap'"),
+    ],
+)
+def test_invalid_mode_arguments_raise(kwargs, expected_message, polygon_hole):
+    with pytest.raises(ValueError, match=expected_message):
+        geohash_polygon.polygon_to_geohashes(polygon_hole, 6, **kwargs)
+
+
+def _cells_touching(geometry, precision):
+    minx, miny, maxx, maxy = geometry.bounds
+    candidates = geohash_polygon.polygon_to_geohashes(
+        shapely.geometry.box(minx, miny, maxx, maxy).buffer(0.05), precision, False
+    )
+    return {
+        h
+        for h in candidates
+        if shapely.geometry.box(*_bbox(h)).intersects(geometry)
+    }
+
+
+def _bbox(geohash):
+    lng, lat, lng_err, lat_err = geohash_polygon.decode_exactly(geohash)
+    return lng - lng_err, lat - lat_err, lng + lng_err, lat + lat_err
+
+
+@pytest.mark.parametrize("precision", [5, 6, 7])
+def test_linestring_matches_intersecting_cells(precision):
+    line = shapely.geometry.LineString([(-73.60, 45.50), (-73.55, 45.53), (-73.57, 45.46)])
+    result = geohash_polygon.linestring_to_geohashes(line, precision)
+    assert result == _cells_touching(line, precision)
+    assert geohash_polygon.polygon_to_geohashes(line, precision) == result
+    assert geohash_polygon.polygon_to_geohashes(line, precision, False) == result
+
+
+def test_multilinestring_is_union_of_parts():
+    a = shapely.geometry.LineString([(-73.60, 45.50), (-73.55, 45.53)])
+    b = shapely.geometry.LineString([(-73.50, 45.40), (-73.45, 45.42)])
+    result = geohash_polygon.linestring_to_geohashes(shapely.geometry.MultiLineString([a, b]), 6)
+    assert result == geohash_polygon.linestring_to_geohashes(
+        a, 6
+    ) | geohash_polygon.linestring_to_geohashes(b, 6)
+
+
+def test_linestring_corridor_grows_with_buffer():
+    line = shapely.geometry.LineString([(-73.60, 45.50), (-73.55, 45.53)])
+    bare = geohash_polygon.linestring_to_geohashes(line, 7)
+    narrow = geohash_polygon.linestring_to_geohashes(line, 7, buffer_m=100)
+    wide = geohash_polygon.linestring_to_geohashes(line, 7, buffer_m=500)
+    assert bare < narrow < wide
+
+
+@pytest.mark.parametrize(
+    "kwargs",
+    [{"inner": True}, {"mode": "contained"}, {"mode": "min_overlap", "min_overlap": 0.5}],
+)
+def test_lines_and_points_reject_areal_modes(kwargs):
+    line = shapely.geometry.LineString([(0, 0), (1, 1)])
+    point = shapely.geometry.Point((0.5, 0.5))
+    for geometry in (line, point):
+        with pytest.raises(ValueError, match="areal geometry"):
+            geohash_polygon.polygon_to_geohashes(geometry, 6, **kwargs)
+
+
+def test_linestring_rejects_bad_input(polygon_verdun):
+    with pytest.raises(ValueError, match="not a LineString or MultiLineString"):
+        geohash_polygon.linestring_to_geohashes(polygon_verdun, 6)
+    line = shapely.geometry.LineString([(0, 0), (1, 1)])
+    with pytest.raises(ValueError, match="buffer_m"):
+        geohash_polygon.linestring_to_geohashes(line, 6, buffer_m=-1)
+
+
+def test_point_maps_to_containing_cell():
+    point = shapely.geometry.Point((-99.1795917, 19.432134))
+    expected = geohash_polygon.encode(-99.1795917, 19.432134, 7)
+    assert geohash_polygon.polygon_to_geohashes(point, 7) == {expected}
+    assert geohash_polygon.polygon_to_geohashes(point, 7, False) == {expected}
+
+
+def test_multipoint_maps_to_containing_cells():
+    coords = [(-73.5673, 45.5017), (2.3522, 48.8566), (-73.5673, 45.5017)]
+    result = geohash_polygon.polygon_to_geohashes(shapely.geometry.MultiPoint(coords), 5)
+    assert result == {geohash_polygon.encode(lng, lat, 5) for lng, lat in coords}
+
+
+def test_geometry_collection_is_union_of_members(polygon_verdun):
+    line = shapely.geometry.LineString([(2.30, 48.80), (2.40, 48.90)])
+    point = shapely.geometry.Point((-99.1795917, 19.432134))
+    collection = shapely.geometry.GeometryCollection([polygon_verdun, line, point])
+    result = geohash_polygon.polygon_to_geohashes(collection, 6, False)
+    assert result == (
+        geohash_polygon.polygon_to_geohashes(polygon_verdun, 6, False)
+        | geohash_polygon.linestring_to_geohashes(line, 6)
+        | {geohash_polygon.encode(-99.1795917, 19.432134, 6)}
+    )
+
+
+def test_mixed_geometry_collection_with_inner(polygon_verdun):
+    line = shapely.geometry.LineString([(2.30, 48.80), (2.40, 48.90)])
+    point = shapely.geometry.Point((-99.1795917, 19.432134))
+    collection = shapely.geometry.GeometryCollection([polygon_verdun, line, point])
+    result = geohash_polygon.polygon_to_geohashes(collection, 6, True)
+    # the polygon keeps only its inner cells; the line and point cover what they touch
+    assert result == (
+        geohash_polygon.polygon_to_geohashes(polygon_verdun, 6, True)
+        | geohash_polygon.linestring_to_geohashes(line, 6)
+        | {geohash_polygon.encode(-99.1795917, 19.432134, 6)}
+    )
+
+
+def _fiji(lng_west, lng_east):
+    return shapely.geometry.Polygon(
+        [(lng_west, -17), (lng_east, -17), (lng_east, -16), (lng_west, -16), (lng_west, -17)]
+    )
+
+
+def _fiji_cells(precision, inner):
+    """Cells of _fiji(179, 181) worked out from the cell bounds alone."""
+    _, _, lng_err, lat_err = geohash_polygon.decode_exactly(
+        geohash_polygon.encode(179.0, -17.0, precision)
+    )
+    cells = set()
+    for i in range(int(3 / (2 * lng_err)) + 2):
+        for j in range(int(2 / (2 * lat_err)) + 2):
+            lng = 178.5 + i * 2 * lng_err
+            h = geohash_polygon.encode((lng + 180) % 360 - 180, -17.5 + j * 2 * lat_err, precision)
+            c_lng, c_lat, _, _ = geohash_polygon.decode_exactly(h)
+            # continuous longitudes: 181 rather than -179
+            west, east = c_lng % 360 - lng_err, c_lng % 360 + lng_err
+            south, north = c_lat - lat_err, c_lat + lat_err
+            if inner:
+                keep = 179 < west and east < 181 and -17 < south and north < -16
+            else:
+                keep = west < 181 and east > 179 and south < -16 and north > -17
+            if keep:
+                cells.add(h)
+    return cells
+
+
+@pytest.mark.parametrize("inner", [True, False])
+def test_antimeridian_polygon_is_continuous(inner):
+    expected = _fiji_cells(4, inner)
+    assert geohash_polygon.polygon_to_geohashes(_fiji(179, -179), 4, inner) == expected
+    assert geohash_polygon.polygon_to_geohashes(_fiji(179, 181), 4, inner) == expected
+    centers = geohash_polygon.decode_many(list(expected))
+    assert all(abs(lng) > 179 for lng, _ in centers)
+
+
+def test_antimeridian_linestring_stays_near_dateline():
+    line = shapely.geometry.LineString([(179.5, 0.0), (-179.5, 0.5)])
+    cells = geohash_polygon.linestring_to_geohashes(line, 5)
+    assert cells
+    assert all(abs(lng) > 179 for lng, _ in geohash_polygon.decode_many(list(cells)))
+
+
+@pytest.mark.parametrize("lat", [80.0, -80.0])
+def test_polar_cap_ring_covers_the_pole(lat):
+    # a ring going once around the pole, as polar geographies are often drawn
+    ring = shapely.geometry.Polygon([(0, lat), (90, lat), (180, lat), (-90, lat), (0, lat)])
+    pole = 90 if lat > 0 else -90
+    band = shapely.geometry.box(-180, min(lat, pole), 180, max(lat, pole))
+    for inner in (True, False):
+        assert geohash_polygon.polygon_to_geohashes(
+            ring, 3, inner
+        ) == geohash_polygon.polygon_to_geohashes(band, 3, inner)
+
+
+def test_polygon_touching_pole_at_89_degrees():
+    svalbard_north = shapely.geometry.box(10, 89, 30, 90)
+    cells = geohash_polygon.polygon_to_geohashes(svalbard_north, 5, False)
+    centers = geohash_polygon.decode_many(list(cells))
+    assert all(lat > 88.9 and 9.9 < lng < 30.1 for lng, lat in centers)
+
+
+# ── cell budget ───────────────────────────────────────────────────────────────
+
+def test_max_cells_raises_too_many_cells(polygon_verdun):
+    full = geohash_polygon.polygon_to_geohashes(polygon_verdun, 7, False)
+    with pytest.raises(geohash_polygon.TooManyCellsError, match="max_cells=10"):
+        geohash_polygon.polygon_to_geohashes(polygon_verdun, 7, False, max_cells=10)
+    assert (
+        geohash_polygon.polygon_to_geohashes(polygon_verdun, 7, False, max_cells=len(full))
+        == full
+    )
+
+
+def test_max_cells_fails_fast_on_huge_polygon():
+    # ~10^11 cells at precision 9: without the budget this would exhaust memory
+    world = shapely.geometry.box(-170, -80, 170, 80)
+    with pytest.raises(geohash_polygon.TooManyCellsError):
+        geohash_polygon.polygon_to_geohashes(world, 9, False, max_cells=100_000)
+
+
+@pytest.mark.parametrize(
+    "cover",
+    [
+        lambda polygon, **kw: geohash_polygon.linestring_to_geohashes(
+            polygon.exterior, 7, buffer_m=200, **kw
+        ),
+        lambda polygon, **kw: geohash_polygon.polygon_to_geohashes_compact(
+            polygon, 4, 7, False, **kw
+        ),
+        lambda polygon, **kw: geohash_polygon.circle_to_geohashes(
+            -73.57, 45.46, 2000.0, 7, False, **kw
+        ),
+    ],
+)
+def test_max_cells_applies_to_every_walk(cover, polygon_verdun):
+    full = cover(polygon_verdun)
+    with pytest.raises(geohash_polygon.TooManyCellsError, match="max_cells=10"):
+        cover(polygon_verdun, max_cells=10)
+    assert cover(polygon_verdun, max_cells=len(full)) == full
+
+
+# ── precision selection ───────────────────────────────────────────────────────
+
+@pytest.mark.parametrize("max_cells", [50, 1_000, 20_000])
+def test_choose_precision_fits_max_cells(max_cells, polygon_verdun):
+    precision = geohash_polygon.choose_precision(polygon_verdun, max_cells=max_cells)
+    assert 1 <= precision <= 12
+    assert len(geohash_polygon.polygon_to_geohashes(polygon_verdun, precision, False)) <= max_cells
+
+
+def test_choose_precision_target_cell_size(polygon_verdun):
+    # by the square root of their area, cells at Verdun's latitude are ~130 m at
+    # precision 7 and ~720 m at precision 6
+    assert geohash_polygon.choose_precision(polygon_verdun, target_cell_m=120.0) == 7
+    assert geohash_polygon.choose_precision(polygon_verdun, target_cell_m=500.0) == 6
+
+
+def test_choose_precision_requires_a_target(polygon_verdun):
+    with pytest.raises(ValueError, match="max_cells or target_cell_m"):
+        geohash_polygon.choose_precision(polygon_verdun)
+    with pytest.raises(ValueError, match="target_cell_m"):
+        geohash_polygon.choose_precision(polygon_verdun, target_cell_m=-1.0)
+    with pytest.raises(geohash_polygon.TooManyCellsError):
+        geohash_polygon.choose_precision(polygon_verdun, max_cells=0)
+
+
+def test_polygon_to_geohashes_auto_uses_chosen_precision(polygon_verdun):
+    precision = geohash_polygon.choose_precision(polygon_verdun, max_cells=1_000)
+    assert geohash_polygon.polygon_to_geohashes_auto(
+        polygon_verdun, False, max_cells=1_000
+    ) == geohash_polygon.polygon_to_geohashes(polygon_verdun, precision, False)
+
+
+def test_polygon_to_geohashes_int_matches_strings(polygon_verdun):
+    hashes = geohash_polygon.polygon_to_geohashes(polygon_verdun, 7, mode="centroid")
+    values = geohash_polygon.polygon_to_geohashes_int(polygon_verdun, 7, mode="centroid")
+    assert values == {geohash_polygon.geohash_to_int(h) for h in hashes}
+    with pytest.raises(geohash_polygon.TooManyCellsError):
+        geohash_polygon.polygon_to_geohashes_int(polygon_verdun, 7, False, max_cells=len(hashes) // 2)